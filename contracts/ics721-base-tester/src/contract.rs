@@ -158,6 +158,9 @@ mod receive_callbacks {
             Ics721Status::Failed(..) => {
                 // Transfer failed, the NFT owner should be the sender
             }
+            Ics721Status::Timeout => {
+                // Transfer timed out, the NFT owner should be the sender
+            }
         }
 
         Ok(Response::new())
@@ -240,6 +243,7 @@ fn execute_send_nft(
                 channel_id,
                 timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(1000)),
                 memo,
+                refund_address: None,
             })?,
         })?,
         funds: vec![],
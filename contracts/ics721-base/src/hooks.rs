@@ -0,0 +1,5 @@
+use ics721::hooks::Ics721Hooks;
+
+use crate::state::Ics721Contract;
+
+impl Ics721Hooks for Ics721Contract {}
@@ -1,4 +1,5 @@
 pub mod execute;
+pub mod hooks;
 pub mod ibc;
 pub mod query;
 pub mod state;
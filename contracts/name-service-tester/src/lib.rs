@@ -0,0 +1,51 @@
+use cosmwasm_schema::cw_serde;
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_json_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult,
+};
+use cw_storage_plus::Map;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// List of names and their associated addresses.
+    pub names: Vec<(String, String)>,
+}
+
+#[cw_serde]
+pub enum QueryMsg {
+    /// Returns the address associated to the name, fails if name is unknown.
+    AssociatedAddress { name: String },
+}
+
+const NAMES: Map<String, String> = Map::new("names");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response> {
+    for (name, address) in msg.names {
+        NAMES.save(deps.storage, name, &address)?;
+    }
+    Ok(Response::default().add_attribute("method", "instantiate"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Err(StdError::generic_err("not implemented"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::AssociatedAddress { name } => {
+            let address = NAMES
+                .may_load(deps.storage, name.clone())?
+                .ok_or_else(|| StdError::not_found(format!("name {name}")))?;
+            to_json_binary(&address)
+        }
+    }
+}
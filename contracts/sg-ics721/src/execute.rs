@@ -1,8 +1,13 @@
 use cosmwasm_std::{
-    from_json, to_json_binary, Addr, Binary, ContractInfoResponse, Deps, DepsMut, Env, StdResult,
+    to_json_binary, Addr, Binary, ContractInfoResponse, Deps, DepsMut, Env, StdResult,
 };
 use cw721::{CollectionExtension, NftExtension, RoyaltyInfo};
-use ics721::{execute::Ics721Execute, state::CollectionData, utils::get_collection_data};
+use ics721::{
+    execute::Ics721Execute,
+    helpers::voucher_creator,
+    state::{CollectionData, ROYALTY_RECIPIENTS, VOUCHER_PROFILES},
+    utils::get_collection_data,
+};
 use ics721_types::token_types::Class;
 
 use sg721::RoyaltyInfoResponse;
@@ -56,6 +61,7 @@ impl Ics721Execute for SgIcs721Contract {
         deps: Deps,
         env: &Env,
         class: &Class,
+        collection_data: Option<CollectionData>,
         cw721_admin: Option<String>,
     ) -> StdResult<Binary> {
         // ics721 creator is used, in case no source owner in class data is provided (e.g. due to nft-transfer module).
@@ -68,6 +74,16 @@ impl Ics721Execute for SgIcs721Contract {
             .or_else(|| admin.clone())
             .or_else(|| Some(creator.clone()))
             .unwrap();
+        let collection_creator = voucher_creator(
+            deps,
+            env,
+            &class.id,
+            cw721_admin_or_ics721_admin_or_ics721_creator.clone(),
+        )?;
+        // royalty recipient claimed by collection owner on home chain
+        let royalty_recipient = ROYALTY_RECIPIENTS
+            .may_load(deps.storage, class.id.clone())?
+            .map(|a| a.to_string());
         let mut instantiate_msg = sg721::InstantiateMsg {
             name: class.id.clone().into(),
             symbol: class.id.clone().into(),
@@ -76,7 +92,7 @@ impl Ics721Execute for SgIcs721Contract {
                 // source owner could be: 1. regular wallet, 2. contract, or 3. multisig
                 // bech32 calculation for 2. and 3. leads to unknown address
                 // therefore, we use ics721 creator as owner
-                creator: cw721_admin_or_ics721_admin_or_ics721_creator.clone(),
+                creator: collection_creator.clone(),
                 description: "".to_string(),
                 // remaining props is set below, in case there's collection data
                 image: STARGAZE_ICON_PLACEHOLDER.to_string(), // use Stargaze icon as placeholder
@@ -88,23 +104,21 @@ impl Ics721Execute for SgIcs721Contract {
         };
 
         // use collection data for setting name and symbol
-        let collection_data = class
-            .data
-            .clone()
-            .and_then(|binary| from_json::<CollectionData>(binary).ok());
         if let Some(collection_data) = collection_data {
             instantiate_msg.name = collection_data.name;
             instantiate_msg.symbol = collection_data.symbol;
             if let Some(collection_info_extension_msg) =
                 collection_data.extension.map(|ext| sg721::CollectionInfo {
-                    creator: cw721_admin_or_ics721_admin_or_ics721_creator.clone(),
+                    creator: collection_creator,
                     description: ext.description,
                     image: ext.image,
                     external_link: ext.external_link,
                     explicit_content: ext.explicit_content,
                     start_trading_time: ext.start_trading_time,
                     royalty_info: ext.royalty_info.map(|r| RoyaltyInfoResponse {
-                        payment_address: cw721_admin_or_ics721_admin_or_ics721_creator, // r.payment_address cant be used, since it is from another chain
+                        // r.payment_address cant be used, since it is from another chain
+                        payment_address: royalty_recipient
+                            .unwrap_or(cw721_admin_or_ics721_admin_or_ics721_creator),
                         share: r.share,
                     }),
                 })
@@ -113,6 +127,22 @@ impl Ics721Execute for SgIcs721Contract {
             }
         }
 
+        // voucher profile takes precedence over collection data
+        if let Some(profile) = VOUCHER_PROFILES.may_load(deps.storage, class.id.clone())? {
+            instantiate_msg.name = profile.name.unwrap_or(instantiate_msg.name);
+            instantiate_msg.symbol = profile.symbol.unwrap_or(instantiate_msg.symbol);
+            let collection_info = &mut instantiate_msg.collection_info;
+            if let Some(description) = profile.description {
+                collection_info.description = description;
+            }
+            if let Some(image) = profile.image {
+                collection_info.image = image;
+            }
+            collection_info.external_link = profile
+                .external_link
+                .or(collection_info.external_link.take());
+        }
+
         to_json_binary(&instantiate_msg)
     }
 
@@ -121,31 +151,28 @@ impl Ics721Execute for SgIcs721Contract {
         token_id: String,
         token_uri: Option<String>,
         owner: String,
-        data: Option<Binary>,
+        extension: Option<NftExtension>,
     ) -> StdResult<Binary> {
-        // parse token data and check whether it is of type NftExtension
-        let extension = data
-            .and_then(|binary| {
-                from_json::<NftExtension>(binary).ok().map(|ext| Metadata {
-                    animation_url: ext.animation_url,
-                    attributes: ext.attributes.map(|traits| {
-                        traits
-                            .into_iter()
-                            .map(|t| Trait {
-                                trait_type: t.trait_type,
-                                value: t.value,
-                                display_type: t.display_type,
-                            })
-                            .collect()
-                    }),
-                    background_color: ext.background_color,
-                    description: ext.description,
-                    external_url: ext.external_url,
-                    image: ext.image,
-                    image_data: ext.image_data,
-                    youtube_url: ext.youtube_url,
-                    name: ext.name,
-                })
+        let extension = extension
+            .map(|ext| Metadata {
+                animation_url: ext.animation_url,
+                attributes: ext.attributes.map(|traits| {
+                    traits
+                        .into_iter()
+                        .map(|t| Trait {
+                            trait_type: t.trait_type,
+                            value: t.value,
+                            display_type: t.display_type,
+                        })
+                        .collect()
+                }),
+                background_color: ext.background_color,
+                description: ext.description,
+                external_url: ext.external_url,
+                image: ext.image,
+                image_data: ext.image_data,
+                youtube_url: ext.youtube_url,
+                name: ext.name,
             })
             .unwrap_or(Metadata {
                 // no onchain metadata (only offchain), in this case empty metadata is created
@@ -0,0 +1,5 @@
+use ics721::hooks::Ics721Hooks;
+
+use crate::state::SgIcs721Contract;
+
+impl Ics721Hooks for SgIcs721Contract {}
@@ -495,6 +495,8 @@ impl Test {
             .query_wasm_smart(
                 self.ics721.clone(),
                 &QueryMsg::OutgoingChannels {
+                    channel_id: None,
+                    class_id: None,
                     start_after: None,
                     limit: None,
                 },
@@ -508,6 +510,8 @@ impl Test {
             .query_wasm_smart(
                 self.ics721.clone(),
                 &QueryMsg::OutgoingChannels {
+                    channel_id: None,
+                    class_id: None,
                     start_after: None,
                     limit: None,
                 },
@@ -2139,6 +2143,7 @@ fn test_no_proxy_unknown_msg() {
                 height: 10,
             }),
             memo: None,
+            refund_address: None,
         })
         .unwrap(),
     };
@@ -2177,6 +2182,7 @@ fn test_no_proxy_unauthorized() {
                 height: 10,
             }),
             memo: None,
+            refund_address: None,
         })
         .unwrap(),
     };
@@ -2283,6 +2289,7 @@ fn test_proxy_authorized() {
                             height: 10,
                         }),
                         memo: None,
+                        refund_address: None,
                     })
                     .unwrap(),
                 })
@@ -2317,6 +2324,7 @@ fn test_receive_nft() {
                         height: 10,
                     }),
                     memo: None,
+                    refund_address: None,
                 })
                 .unwrap(),
             }),
@@ -2394,6 +2402,7 @@ fn test_no_receive_with_proxy() {
                         height: 10,
                     }),
                     memo: None,
+                    refund_address: None,
                 })
                 .unwrap(),
             }),
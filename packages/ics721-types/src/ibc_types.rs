@@ -97,6 +97,32 @@ pub struct IbcOutgoingMsg {
     pub timeout: IbcTimeout,
     /// Memo to add custom string to the msg
     pub memo: Option<String>,
+    /// The address NFTs are returned to, in case transfer fails or times out.
+    /// If none, NFTs are returned to the sender.
+    pub refund_address: Option<String>,
+}
+
+/// Packets sent between ICS721 contracts, not carrying any NFTs. Unlike
+/// `NonFungibleTokenPacketData` these are serialized in snake case, e.g.
+/// `{"transfer_creatorship": {...}}`.
+#[cw_serde]
+pub enum Ics721ControlPacket {
+    /// Sent by the ICS721 contract on the home chain, on behalf of the
+    /// collection owner. Transfers creatorship of the voucher collection
+    /// on the receiving chain to the new creator.
+    TransferCreatorship {
+        /// Class ID of the collection on the home chain.
+        class_id: ClassId,
+        /// Collection data, holding the owner of the collection on the
+        /// home chain.
+        class_data: Binary,
+        /// The collection owner on the home chain.
+        sender: String,
+        /// The new creator of the voucher collection on the receiving
+        /// chain. If none, the sender's address converted to the
+        /// receiving chain is used.
+        new_creator: Option<String>,
+    },
 }
 
 #[cw_serde]
@@ -1,5 +1,5 @@
 use cosmwasm_schema::{cw_serde, schemars::JsonSchema};
-use cosmwasm_std::{Binary, IbcPacket};
+use cosmwasm_std::{Binary, IbcPacket, Uint64};
 use serde::{Deserialize, Serialize};
 
 use crate::ibc_types::NonFungibleTokenPacketData;
@@ -27,6 +27,109 @@ pub struct Ics721Callbacks {
     /// The address that will receive the callback message
     /// Defaults to the receiver address
     pub receive_callback_addr: Option<String>,
+    /// Data to pass with a callback on source side in case of a timeout
+    /// Note - If this field is empty, `ack_callback_data` is used instead
+    // skipped when empty, so memos stay parsable by counterparties unaware of this field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_callback_data: Option<Binary>,
+    /// The address that will receive the timeout callback message
+    /// Defaults to the sender address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_callback_addr: Option<String>,
+}
+
+/// ADR-8 callbacks memo in plain JSON, as used by ibc-go's callbacks middleware.
+/// Unknown keys are allowed, since the memo may be shared with other middlewares.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+#[serde(crate = "cosmwasm_schema::serde")]
+pub struct Adr8Memo {
+    /// Callback on source side, mapped onto `Ics721AckCallback`
+    pub src_callback: Option<Adr8Callback>,
+    /// Callback on destination side, mapped onto `Ics721ReceiveCallback`
+    pub dest_callback: Option<Adr8Callback>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+#[serde(crate = "cosmwasm_schema::serde")]
+pub struct Adr8Callback {
+    /// The contract that will receive the callback message
+    pub address: String,
+    /// Gas limit for the callback, only applied on source side
+    pub gas_limit: Option<Uint64>,
+    /// Custom data to pass with the callback, defaults to empty
+    pub msg: Option<Binary>,
+}
+
+impl From<Adr8Memo> for Ics721Callbacks {
+    fn from(memo: Adr8Memo) -> Self {
+        let (ack_callback_data, ack_callback_addr) = match memo.src_callback {
+            Some(callback) => (
+                Some(callback.msg.unwrap_or_default()),
+                Some(callback.address),
+            ),
+            None => (None, None),
+        };
+        let (receive_callback_data, receive_callback_addr) = match memo.dest_callback {
+            Some(callback) => (
+                Some(callback.msg.unwrap_or_default()),
+                Some(callback.address),
+            ),
+            None => (None, None),
+        };
+        Ics721Callbacks {
+            ack_callback_data,
+            ack_callback_addr,
+            receive_callback_data,
+            receive_callback_addr,
+            timeout_callback_data: None,
+            timeout_callback_addr: None,
+        }
+    }
+}
+
+/// ibc-hooks style memo in plain JSON: `{"wasm":{"contract":…,"msg":…}}`.
+/// On receive, ics721 escrows the NFTs and sends them to `contract` via cw721's `SendNft`.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+#[serde(crate = "cosmwasm_schema::serde")]
+pub struct Ics721WasmMemo {
+    pub wasm: Option<Ics721WasmHook>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+#[serde(crate = "cosmwasm_schema::serde")]
+pub struct Ics721WasmHook {
+    /// The contract receiving the NFTs, must be the same as the packet's receiver
+    pub contract: String,
+    /// The msg passed in `Cw721ReceiveMsg` to the contract
+    pub msg: Binary,
+}
+
+/// Opt-in memo in plain JSON: `{"send_nft":{"msg":…}}`.
+/// On receive, ics721 escrows the NFTs and delivers them to the receiver via cw721's `SendNft`,
+/// so receiving contracts get notified by `Cw721ReceiveMsg`.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+#[serde(crate = "cosmwasm_schema::serde")]
+pub struct Ics721SendNftMemo {
+    pub send_nft: Option<Ics721SendNft>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[schemars(crate = "cosmwasm_schema::schemars")]
+#[serde(crate = "cosmwasm_schema::serde")]
+pub struct Ics721SendNft {
+    /// The msg passed in `Cw721ReceiveMsg` to the receiver, defaults to empty
+    pub msg: Option<Binary>,
 }
 
 /// A message is that is being called on receiving the NFT after transfer was completed.
@@ -42,19 +145,57 @@ pub struct Ics721ReceiveCallbackMsg {
 /// A message to update your contract of the status of a transfer
 /// status = Ics721Status::Success - the transfer was successful and NFT is on the other chain
 /// status = Ics721Status::Failed - Transfer failed and contract still owns the NFT
+/// status = Ics721Status::Timeout - Transfer timed out and contract still owns the NFT
 #[cw_serde]
 pub struct Ics721AckCallbackMsg {
     pub status: Ics721Status,
     pub nft_contract: String,
     pub original_packet: NonFungibleTokenPacketData,
     pub msg: Binary,
+    /// Result provided by the receiving chain in case of success.
+    /// It is None on failure or in case counterparty only acks with legacy "1" result.
+    // skipped when empty, so callbacks stay parsable by receivers unaware of this field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ack_result: Option<Ics721AckResult>,
+}
+
+/// Result data of a success ack. It is wrapped in `Ics721Ack::Result`, so the ack
+/// stays compatible with ICS-20 style acks.
+#[cw_serde]
+pub struct Ics721AckResult {
+    /// The voucher (or home) NFT contract on the receiving chain holding the NFTs.
+    pub nft_contract: String,
+    /// Whether vouchers were minted or escrowed NFTs were redeemed on the receiving chain.
+    pub action: Ics721ReceiveAction,
+}
+
+/// The action taken by the receiving chain for an incoming transfer.
+#[cw_serde]
+pub enum Ics721ReceiveAction {
+    /// New vouchers have been minted.
+    Mint,
+    /// NFTs previously sent out have been transferred back from escrow.
+    Redemption,
+}
+
+/// Same as serialized name, e.g. for event attributes.
+impl std::fmt::Display for Ics721ReceiveAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ics721ReceiveAction::Mint => write!(f, "mint"),
+            Ics721ReceiveAction::Redemption => write!(f, "redemption"),
+        }
+    }
 }
 
 /// The status of a transfer on callback
 #[cw_serde]
 pub enum Ics721Status {
     Success,
+    /// Counterparty rejected the transfer.
     Failed(String),
+    /// Packet timed out and never reached the counterparty.
+    Timeout,
 }
 
 /// This is a wrapper for ics721 callbacks
@@ -77,3 +218,44 @@ pub enum ReceiverExecuteMsg {
         data: NonFungibleTokenPacketData,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{from_json, to_json_string};
+
+    use super::*;
+    use crate::token_types::{ClassId, TokenId};
+
+    #[test]
+    fn test_ack_callback_msg_without_ack_result() {
+        let callback = Ics721AckCallbackMsg {
+            status: Ics721Status::Success,
+            nft_contract: "nft_contract".to_string(),
+            original_packet: NonFungibleTokenPacketData {
+                class_id: ClassId::new("id"),
+                class_uri: None,
+                class_data: None,
+                token_ids: vec![TokenId::new("1")],
+                token_uris: None,
+                token_data: None,
+                sender: "violet".to_string(),
+                receiver: "blue".to_string(),
+                memo: None,
+            },
+            msg: Binary::default(),
+            ack_result: None,
+        };
+
+        // none is skipped, so receivers unaware of `ack_result` can still parse the callback
+        let json = to_json_string(&callback).unwrap();
+        assert!(!json.contains("ack_result"));
+        assert_eq!(from_json::<Ics721AckCallbackMsg>(json).unwrap(), callback);
+    }
+
+    #[test]
+    fn test_receive_action_display() {
+        for action in [Ics721ReceiveAction::Mint, Ics721ReceiveAction::Redemption] {
+            assert_eq!(format!("\"{}\"", action), to_json_string(&action).unwrap());
+        }
+    }
+}
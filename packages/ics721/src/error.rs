@@ -68,4 +68,67 @@ pub enum ContractError {
 
     #[error("Couldn't find class id for nft contract: {0}")]
     NoClassIdForNftContract(String),
+
+    #[error("Packet failure can only be handled for failed or timed out transfers")]
+    UnexpectedAckStatus {},
+
+    #[error("Wasm hook contract {contract} must be the receiver {receiver}")]
+    WasmHookReceiverMismatch { receiver: String, contract: String },
+
+    #[error("Invalid proof for claiming NFTs of receiver: {0}")]
+    InvalidClaimProof(String),
+
+    #[error("No claims for receiver: {0}")]
+    NoClaims(String),
+
+    #[error("Inbox mode is disabled")]
+    InboxDisabled {},
+
+    #[error("Unable to resolve name {name} using name service {name_service}")]
+    NameNotResolved { name: String, name_service: String },
+
+    #[error("No pending refund")]
+    NoPendingRefund {},
+
+    #[error("No pending outgoing packet")]
+    NoPendingOutgoingPacket {},
+
+    #[error("No in-flight packet with sequence {sequence} on channel {channel}")]
+    NoInFlightPacket { channel: String, sequence: u64 },
+
+    #[error("Packet with sequence {sequence} on channel {channel} has been voided")]
+    PacketVoided { channel: String, sequence: u64 },
+
+    #[error("Class ID prefix must not be empty")]
+    EmptyClassIdPrefix {},
+
+    #[error("Class ID {0} already has an NFT contract")]
+    ClassIdAlreadyRegistered(String),
+
+    #[error("NFT contract {0} is already registered for a class ID")]
+    NftContractAlreadyRegistered(String),
+
+    #[error("ICS721 is not minter of NFT contract {0}")]
+    NotMinter(String),
+
+    #[error("No collection owner in class data of class ID {0}")]
+    NoCollectionOwner(String),
+
+    #[error("Invalid proof for collection owner: {0}")]
+    InvalidOwnerProof(String),
+
+    #[error("Collection {0} is not native to this chain")]
+    NotHomeCollection(String),
+
+    #[error("{sender} is not owner of collection with class ID {class_id}")]
+    NotCollectionOwner { sender: String, class_id: String },
+
+    #[error("ICS721 is not creator of NFT contract {0}")]
+    NotCreator(String),
+
+    #[error("Control packets are not enabled on channel {0}")]
+    ControlPacketsDisabled(String),
+
+    #[error("Metadata in unknown format: {0}")]
+    UnknownMetadataFormat(String),
 }
@@ -1,43 +1,69 @@
 use std::fmt::Debug;
 
 use cosmwasm_std::{
-    from_json, to_json_binary, Addr, Binary, ContractInfoResponse, Deps, DepsMut, Empty, Env,
-    Event, IbcMsg, MessageInfo, Order, Response, StdResult, SubMsg, WasmMsg,
+    from_json, to_json_binary, Addr, Binary, ContractInfoResponse, CosmosMsg, Decimal, Deps,
+    DepsMut, Empty, Env, Event, IbcMsg, IbcTimeout, MessageInfo, Order, Response, StdResult,
+    SubMsg, WasmMsg,
 };
 use cw721::{
     msg::{NftExtensionMsg, RoyaltyInfoResponse},
     NftExtension,
 };
-use cw_storage_plus::Map;
+use cw_storage_plus::{Bound, Map};
 use ics721_types::{
-    ibc_types::{IbcOutgoingMsg, IbcOutgoingProxyMsg, NonFungibleTokenPacketData},
-    token_types::{Class, ClassId, Token, TokenId},
+    ibc_types::{
+        IbcOutgoingMsg, IbcOutgoingProxyMsg, Ics721ControlPacket, NonFungibleTokenPacketData,
+    },
+    token_types::{Class, ClassId, ClassToken, Token, TokenId},
+    types::Ics721Status,
 };
 use serde::{de::DeserializeOwned, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::{
-    helpers::get_instantiate2_address,
+    helpers::{
+        ack_callback_msg, add_history, admin_clean_event, admin_clean_history, assert_admin,
+        check_metadata, clean_incoming, clean_outgoing, control_packets_enabled,
+        get_instantiate2_address, is_home_chain_owner, is_voucher_creator, load_expired_claims,
+        outgoing_history, plan_clean_and_burn, plan_clean_and_unescrow, update_stats,
+        verify_claim_proof, verify_owner_proof, voucher_creator, voucher_cw721_code_id,
+    },
+    hooks::Ics721Hooks,
     ibc::{
-        INSTANTIATE_CW721_REPLY_ID, INSTANTIATE_INCOMING_PROXY_REPLY_ID,
-        INSTANTIATE_OUTGOING_PROXY_REPLY_ID,
+        refund_messages, INSTANTIATE_CW721_REPLY_ID, INSTANTIATE_INCOMING_PROXY_REPLY_ID,
+        INSTANTIATE_OUTGOING_PROXY_REPLY_ID, REFUND_REPLY_ID, SEND_PACKET_REPLY_ID,
+    },
+    msg::{
+        AdminCleanAction, AdminCleanAndBurnEntry, AdminCleanAndUnescrowEntry, CallbackMsg,
+        ClaimPosition, ClaimProof, Cw721CodeIdTarget, ExecuteMsg, InstantiateMsg, MigrateMsg,
     },
-    msg::{CallbackMsg, ExecuteMsg, InstantiateMsg, MigrateMsg},
     query::{
         load_class_id_for_nft_contract, load_nft_contract_for_class_id,
         query_nft_contract_for_class_id, query_nft_contracts,
     },
     state::{
-        ClassIdInfo, CollectionData, UniversalAllNftInfoResponse, CLASS_ID_AND_NFT_CONTRACT_INFO,
-        CLASS_ID_TO_CLASS, CONTRACT_ADDR_LENGTH, CW721_ADMIN, CW721_CODE_ID,
-        IBC_RECEIVE_TOKEN_METADATA, INCOMING_CLASS_TOKEN_TO_CHANNEL, INCOMING_PROXY,
-        OUTGOING_CLASS_TOKEN_TO_CHANNEL, OUTGOING_PROXY, PO,
+        Claim, ClassIdInfo, CollectionData, HistoryAction, HistoryConfig, HistoryOutcome,
+        InboxConfig, OutgoingPacket, UniversalAllNftInfoResponse, UnknownMetadataPolicy,
+        VoucherFlavour, VoucherProfile, CHANNEL_CW721_CODE_IDS, CLAIMS,
+        CLASS_ID_AND_NFT_CONTRACT_INFO, CLASS_ID_TO_CLASS, CLASS_PREFIX_CW721_CODE_IDS,
+        CONTRACT_ADDR_LENGTH, CONTROL_PACKET_CHANNELS, CONVERT_RECEIVER_PREFIX, CW721_ADMIN,
+        CW721_CODE_FLAVOURS, CW721_CODE_ID, HISTORY, HISTORY_CONFIG, IBC_RECEIVE_TOKEN_METADATA,
+        INBOX_CONFIG, INCOMING_CLASS_TOKEN_TO_CHANNEL, INCOMING_PROXY, NAME_SERVICE,
+        OUTGOING_CLASS_TOKEN_TO_CHANNEL, OUTGOING_PACKETS, OUTGOING_PROXY, OUTGOING_REFUND_ADDRESS,
+        PENDING_OUTGOING_PACKETS, PO, RETURNED_CLAIMS, ROYALTY_RECIPIENTS,
+        ROYALTY_RECIPIENT_NONCES, SEND_NFT_TO_CONTRACTS, UNKNOWN_METADATA_POLICY, VOUCHER_PROFILES,
     },
     token_types::{VoucherCreation, VoucherRedemption},
+    utils::{legacy_voucher_init_msg, legacy_voucher_mint_msg, query_minter},
     ContractError,
 };
 
-pub trait Ics721Execute<T = Empty>
+/// Default number of voucher contracts migrated by `MigrateVoucherContracts`.
+const DEFAULT_MIGRATE_VOUCHER_CONTRACTS_LIMIT: u32 = 10;
+/// Default number of channel entries re-indexed by `MigrateChannelIndexes`.
+const DEFAULT_MIGRATE_CHANNEL_INDEXES_LIMIT: u32 = 100;
+
+pub trait Ics721Execute<T = Empty>: Ics721Hooks<T>
 where
     T: Serialize + DeserializeOwned + Clone,
 {
@@ -134,6 +160,107 @@ where
             } => self.execute_admin_clean_and_unescrow_nft(
                 deps, env, info, recipient, token_id, class_id, collection,
             ),
+            ExecuteMsg::AdminCleanAndBurnNfts { entries } => {
+                self.execute_admin_clean_and_burn_nfts(deps, env, info, entries)
+            }
+            ExecuteMsg::AdminCleanAndUnescrowNfts { entries } => {
+                self.execute_admin_clean_and_unescrow_nfts(deps, env, info, entries)
+            }
+            ExecuteMsg::AdminSetSendNftToContracts { enabled } => {
+                self.execute_admin_set_send_nft_to_contracts(deps, env, info, enabled)
+            }
+            ExecuteMsg::AdminSetInbox { config } => {
+                self.execute_admin_set_inbox(deps, env, info, config)
+            }
+            ExecuteMsg::Claim {
+                receiver,
+                recipient,
+                proof,
+                limit,
+            } => self.execute_claim(deps, env, info, receiver, recipient, proof, limit),
+            ExecuteMsg::ReturnExpiredClaims { start_after, limit } => {
+                self.execute_return_expired_claims(deps, env, start_after, limit)
+            }
+            ExecuteMsg::AdminSetReceiverConversion {
+                channel_id,
+                enabled,
+            } => self.execute_admin_set_receiver_conversion(deps, env, info, channel_id, enabled),
+            ExecuteMsg::AdminSetNameService { name_service } => {
+                self.execute_admin_set_name_service(deps, env, info, name_service)
+            }
+            ExecuteMsg::RecoverPacket { channel, sequence } => {
+                self.execute_recover_packet(deps, env, info, channel, sequence)
+            }
+            ExecuteMsg::AdminSetHistory { config } => {
+                self.execute_admin_set_history(deps, env, info, config)
+            }
+            ExecuteMsg::PruneHistory { limit } => self.execute_prune_history(deps, env, limit),
+            ExecuteMsg::AdminSetVoucherFlavour { code_id, flavour } => {
+                self.execute_admin_set_voucher_flavour(deps, env, info, code_id, flavour)
+            }
+            ExecuteMsg::AdminSetUnknownMetadataPolicy { policy } => {
+                self.execute_admin_set_unknown_metadata_policy(deps, env, info, policy)
+            }
+            ExecuteMsg::AdminSetCw721CodeIdOverride { target, code_id } => {
+                self.execute_admin_set_cw721_code_id_override(deps, env, info, target, code_id)
+            }
+            ExecuteMsg::SetRoyaltyRecipient {
+                class_id,
+                payment_address,
+                proof,
+            } => self.execute_set_royalty_recipient(
+                deps,
+                env,
+                info,
+                class_id,
+                payment_address,
+                proof,
+            ),
+            ExecuteMsg::SetVoucherProfile { class_id, profile } => {
+                self.execute_set_voucher_profile(deps, env, info, class_id, profile)
+            }
+            ExecuteMsg::RegisterVoucherContract { class_id, contract } => {
+                self.execute_register_voucher_contract(deps, env, info, class_id, contract)
+            }
+            ExecuteMsg::AdminSetControlPackets {
+                channel_id,
+                enabled,
+            } => self.execute_admin_set_control_packets(deps, env, info, channel_id, enabled),
+            ExecuteMsg::TransferCreatorship {
+                collection,
+                channel_id,
+                new_creator,
+                timeout,
+            } => self.execute_transfer_creatorship(
+                deps,
+                env,
+                info,
+                collection,
+                channel_id,
+                new_creator,
+                timeout,
+            ),
+            ExecuteMsg::MigrateVoucherContracts {
+                new_code_id,
+                msg,
+                start_after,
+                limit,
+            } => self.execute_migrate_voucher_contracts(
+                deps,
+                env,
+                info,
+                new_code_id,
+                msg,
+                start_after,
+                limit,
+            ),
+            ExecuteMsg::MigrateChannelIndexes {
+                incoming,
+                start_after,
+                limit,
+            } => {
+                self.execute_migrate_channel_indexes(deps, env, info, incoming, start_after, limit)
+            }
         }
     }
 
@@ -150,154 +277,869 @@ where
     ) -> Result<Response<T>, ContractError> {
         deps.api.addr_validate(&owner)?;
         // only admin can call this method
-        let ContractInfoResponse { admin, .. } = deps
-            .querier
-            .query_wasm_contract_info(env.contract.address.to_string())?;
-        if admin.is_some() && info.sender != admin.unwrap() {
-            return Err(ContractError::Unauthorized {});
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+
+        let entry = AdminCleanAndBurnEntry {
+            owner,
+            token_id,
+            class_id: child_class_id,
+            collection: child_collection,
+        };
+        let burn_msg = plan_clean_and_burn(deps.as_ref(), &entry)?;
+        clean_incoming(deps.storage, &entry.class_id, &entry.token_id)?;
+        if burn_msg.is_some() {
+            let action = AdminCleanAction::Burn {
+                owner: entry.owner.clone(),
+            };
+            add_history(
+                deps.storage,
+                admin_clean_history(&entry.class_id, &entry.token_id, &action, env.block.height),
+            )?;
         }
 
-        // check given child class id and child collection is the same as stored in the contract
-        let token_id = TokenId::new(token_id);
-        let child_class_id = ClassId::new(child_class_id);
-        let child_collection = deps.api.addr_validate(&child_collection)?;
-        match query_nft_contract_for_class_id(deps.storage, child_class_id.clone())? {
-            Some(cw721_addr) => {
-                if cw721_addr != child_collection {
-                    return Err(ContractError::NoNftContractMatch {
-                        child_collection: child_collection.to_string(),
-                        class_id: child_class_id.to_string(),
-                        token_id: token_id.into(),
-                        cw721_addr: cw721_addr.to_string(),
-                    });
-                }
-            }
-            None => {
-                return Err(ContractError::NoNftContractForClassId(
-                    child_class_id.to_string(),
-                ))
-            }
+        Ok(Response::default()
+            .add_attribute("method", "execute_admin_clean_and_burn_nft")
+            .add_messages(burn_msg))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_admin_clean_and_unescrow_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipient: String,
+        token_id: String,
+        home_class_id: String,
+        home_collection: String,
+    ) -> Result<Response<T>, ContractError> {
+        deps.api.addr_validate(&recipient)?;
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+
+        let entry = AdminCleanAndUnescrowEntry {
+            recipient,
+            token_id,
+            class_id: home_class_id,
+            collection: home_collection,
+        };
+        let transfer_msg = plan_clean_and_unescrow(deps.as_ref(), &env, &entry)?;
+        clean_outgoing(deps.storage, &entry.class_id, &entry.token_id)?;
+        if transfer_msg.is_some() {
+            let action = AdminCleanAction::Transfer {
+                recipient: entry.recipient.clone(),
+            };
+            add_history(
+                deps.storage,
+                admin_clean_history(&entry.class_id, &entry.token_id, &action, env.block.height),
+            )?;
         }
 
-        // remove incoming channel entry and metadata
-        INCOMING_CLASS_TOKEN_TO_CHANNEL
-            .remove(deps.storage, (child_class_id.clone(), token_id.clone()));
-        IBC_RECEIVE_TOKEN_METADATA.remove(deps.storage, (child_class_id.clone(), token_id.clone()));
+        Ok(Response::default()
+            .add_attribute("method", "execute_admin_clean_and_unescrow_nft")
+            .add_messages(transfer_msg))
+    }
 
-        // check NFT on child collection owned by recipient
-        let maybe_nft_info: Option<UniversalAllNftInfoResponse> = deps
-            .querier
-            .query_wasm_smart(
-                child_collection.clone(),
-                &cw721_metadata_onchain::msg::QueryMsg::AllNftInfo {
-                    token_id: token_id.clone().into(),
-                    include_expired: None,
+    /// Batch version of `AdminCleanAndBurnNft`. All entries are cleaned up atomically,
+    /// an event is emitted for each token.
+    fn execute_admin_clean_and_burn_nfts(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        entries: Vec<AdminCleanAndBurnEntry>,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+
+        let mut response =
+            Response::default().add_attribute("method", "execute_admin_clean_and_burn_nfts");
+        for entry in entries {
+            deps.api.addr_validate(&entry.owner)?;
+            let burn_msg = plan_clean_and_burn(deps.as_ref(), &entry)?;
+            clean_incoming(deps.storage, &entry.class_id, &entry.token_id)?;
+            let action = match burn_msg {
+                Some(_) => AdminCleanAction::Burn {
+                    owner: entry.owner.clone(),
                 },
-            )
-            .ok();
+                None => AdminCleanAction::CleanStateOnly {},
+            };
+            add_history(
+                deps.storage,
+                admin_clean_history(&entry.class_id, &entry.token_id, &action, env.block.height),
+            )?;
+            response = response.add_messages(burn_msg).add_event(admin_clean_event(
+                &entry.class_id,
+                &entry.token_id,
+                &action,
+            ));
+        }
+        Ok(response)
+    }
+
+    /// Batch version of `AdminCleanAndUnescrowNft`. All entries are cleaned up atomically,
+    /// an event is emitted for each token.
+    fn execute_admin_clean_and_unescrow_nfts(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        entries: Vec<AdminCleanAndUnescrowEntry>,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
 
         let mut response =
-            Response::default().add_attribute("method", "execute_admin_clean_and_burn_nft");
-        if let Some(UniversalAllNftInfoResponse { access, .. }) = maybe_nft_info {
-            if access.owner != owner {
-                return Err(ContractError::NotOwnerOfNft {
-                    recipient: owner.to_string(),
-                    token_id: token_id.clone().into(),
-                    owner: access.owner.to_string(),
-                });
-            }
-            // burn child NFT
-            // note: this requires approval from recipient, or recipient burns it himself
-            let burn_msg = WasmMsg::Execute {
-                contract_addr: child_collection.to_string(),
-                msg: to_json_binary(&cw721_metadata_onchain::msg::ExecuteMsg::Burn {
-                    token_id: token_id.clone().into(),
-                })?,
-                funds: vec![],
+            Response::default().add_attribute("method", "execute_admin_clean_and_unescrow_nfts");
+        for entry in entries {
+            deps.api.addr_validate(&entry.recipient)?;
+            let transfer_msg = plan_clean_and_unescrow(deps.as_ref(), &env, &entry)?;
+            clean_outgoing(deps.storage, &entry.class_id, &entry.token_id)?;
+            let action = match transfer_msg {
+                Some(_) => AdminCleanAction::Transfer {
+                    recipient: entry.recipient.clone(),
+                },
+                None => AdminCleanAction::CleanStateOnly {},
             };
-            response = response.add_message(burn_msg);
+            add_history(
+                deps.storage,
+                admin_clean_history(&entry.class_id, &entry.token_id, &action, env.block.height),
+            )?;
+            response = response
+                .add_messages(transfer_msg)
+                .add_event(admin_clean_event(&entry.class_id, &entry.token_id, &action));
         }
+        Ok(response)
+    }
 
+    fn execute_admin_set_send_nft_to_contracts(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        enabled: bool,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        SEND_NFT_TO_CONTRACTS.save(deps.storage, &enabled)?;
+
+        Ok(Response::default()
+            .add_attribute("method", "execute_admin_set_send_nft_to_contracts")
+            .add_attribute("enabled", enabled.to_string()))
+    }
+
+    fn execute_admin_set_receiver_conversion(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        channel_id: String,
+        enabled: bool,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        CONVERT_RECEIVER_PREFIX.save(deps.storage, channel_id.clone(), &enabled)?;
+
+        Ok(Response::default()
+            .add_attribute("method", "execute_admin_set_receiver_conversion")
+            .add_attribute("channel_id", channel_id)
+            .add_attribute("enabled", enabled.to_string()))
+    }
+
+    /// Refunds all NFTs of an in-flight packet, which can't be acked nor timed out (e.g. light client expired or frozen).
+    /// Packet is voided, so any later ack or timeout is rejected.
+    fn execute_recover_packet(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        channel: String,
+        sequence: u64,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        let key = (channel.clone(), sequence);
+        let mut outgoing_packet = OUTGOING_PACKETS
+            .may_load(deps.storage, key.clone())?
+            .ok_or_else(|| ContractError::NoInFlightPacket {
+                channel: channel.clone(),
+                sequence,
+            })?;
+        if outgoing_packet.voided {
+            return Err(ContractError::PacketVoided { channel, sequence });
+        }
+        outgoing_packet.voided = true;
+        OUTGOING_PACKETS.save(deps.storage, key, &outgoing_packet)?;
+
+        let message = outgoing_packet.data;
+        let nft_contract =
+            load_nft_contract_for_class_id(deps.storage, message.class_id.to_string())?;
+        let (refunds, refund_addresses) =
+            refund_messages(deps.branch(), &message, &nft_contract, &channel)?;
+        add_history(
+            deps.storage,
+            outgoing_history(
+                HistoryAction::Refund,
+                &message,
+                &channel,
+                Some(sequence),
+                env.block.height,
+                HistoryOutcome::Failed {
+                    error: "packet recovered".to_string(),
+                },
+            ),
+        )?;
+
+        let mut response = Response::default()
+            .add_submessages(
+                refunds
+                    .into_iter()
+                    .map(|refund| SubMsg::reply_always(refund, REFUND_REPLY_ID)),
+            )
+            .add_attribute("method", "execute_recover_packet")
+            .add_attribute("channel_id", channel)
+            .add_attribute("sequence", sequence.to_string())
+            .add_attribute("token_ids", format!("{:?}", message.token_ids))
+            .add_attribute("class_id", message.class_id.to_string())
+            .add_attribute("address_refunded", refund_addresses.join(","));
+        // notify sender, same as on ack fail
+        if let Some(callback) = ack_callback_msg(
+            deps.as_ref(),
+            Ics721Status::Failed("packet recovered".to_string()),
+            message,
+            nft_contract.to_string(),
+            None,
+        ) {
+            response = response.add_submessage(callback);
+        }
         Ok(response)
     }
 
-    #[allow(clippy::too_many_arguments)]
-    fn execute_admin_clean_and_unescrow_nft(
+    fn execute_admin_set_name_service(
         &self,
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        recipient: String,
-        token_id: String,
-        home_class_id: String,
-        home_collection: String,
+        name_service: Option<String>,
     ) -> Result<Response<T>, ContractError> {
-        deps.api.addr_validate(&recipient)?;
         // only admin can call this method
-        let ContractInfoResponse { admin, .. } = deps
-            .querier
-            .query_wasm_contract_info(env.contract.address.to_string())?;
-        if admin.is_some() && info.sender != admin.unwrap() {
-            return Err(ContractError::Unauthorized {});
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        let name_service = name_service
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        match &name_service {
+            Some(name_service) => NAME_SERVICE.save(deps.storage, name_service)?,
+            None => NAME_SERVICE.remove(deps.storage),
         }
 
-        // check given home class id and home collection is the same as stored in the contract
-        let home_class_id = ClassId::new(home_class_id);
-        let home_collection = deps.api.addr_validate(&home_collection)?;
-        match query_nft_contract_for_class_id(deps.storage, home_class_id.clone())? {
-            Some(cw721_addr) => {
-                if cw721_addr != home_collection {
-                    return Err(ContractError::NoNftContractMatch {
-                        child_collection: home_collection.to_string(),
-                        class_id: home_class_id.to_string(),
-                        token_id,
-                        cw721_addr: cw721_addr.to_string(),
-                    });
-                }
+        Ok(Response::default()
+            .add_attribute("method", "execute_admin_set_name_service")
+            .add_attribute(
+                "name_service",
+                name_service.map_or_else(|| "none".to_string(), |or| or.to_string()),
+            ))
+    }
+
+    fn execute_admin_set_inbox(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        config: Option<InboxConfig>,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        let response = Response::default().add_attribute("method", "execute_admin_set_inbox");
+        match config {
+            Some(config) => {
+                INBOX_CONFIG.save(deps.storage, &config)?;
+                Ok(response
+                    .add_attribute("claim_expiry", config.claim_expiry.to_string())
+                    .add_attribute("return_timeout", config.return_timeout.to_string()))
             }
             None => {
-                return Err(ContractError::NoNftContractForClassId(
-                    home_class_id.to_string(),
-                ))
+                INBOX_CONFIG.remove(deps.storage);
+                Ok(response.add_attribute("inbox", "disabled"))
             }
         }
+    }
 
-        // remove outgoing channel entry
-        let token_id = TokenId::new(token_id);
-        OUTGOING_CLASS_TOKEN_TO_CHANNEL
-            .remove(deps.storage, (home_class_id.clone(), token_id.clone()));
+    /// Transfers NFTs held in custody for the raw receiver to recipient,
+    /// once sender proved to be the owner of receiver.
+    #[allow(clippy::too_many_arguments)]
+    fn execute_claim(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        receiver: String,
+        recipient: String,
+        proof: ClaimProof,
+        limit: Option<u32>,
+    ) -> Result<Response<T>, ContractError> {
+        let recipient = deps.api.addr_validate(&recipient)?;
+        verify_claim_proof(
+            deps.as_ref(),
+            &env,
+            &info.sender,
+            &receiver,
+            recipient.as_str(),
+            proof,
+        )?;
 
-        // check NFT on home collection owned by ics721 contract
-        let maybe_nft_info: Option<UniversalAllNftInfoResponse> = deps
-            .querier
-            .query_wasm_smart(
-                home_collection.clone(),
-                &cw721_metadata_onchain::msg::QueryMsg::AllNftInfo {
-                    token_id: token_id.clone().into(),
-                    include_expired: None,
-                },
-            )
-            .ok();
+        let all = CLAIMS
+            .prefix(receiver.clone())
+            .range(deps.storage, None, None, Order::Ascending);
+        let claims = match limit {
+            Some(limit) => all.take(limit as usize).collect::<StdResult<Vec<_>>>(),
+            None => all.collect::<StdResult<Vec<_>>>(),
+        }?;
+        if claims.is_empty() {
+            return Err(ContractError::NoClaims(receiver));
+        }
 
-        let mut response =
-            Response::default().add_attribute("method", "execute_admin_clean_and_unescrow_nft");
-        if let Some(UniversalAllNftInfoResponse { access, .. }) = maybe_nft_info {
-            if access.owner != env.contract.address {
-                return Err(ContractError::NotEscrowedByIcs721(access.owner.to_string()));
-            }
-            // transfer NFT
-            let transfer_msg = WasmMsg::Execute {
-                contract_addr: home_collection.to_string(),
+        let mut response = Response::default()
+            .add_attribute("method", "execute_claim")
+            .add_attribute("receiver", receiver.clone())
+            .add_attribute("recipient", recipient.to_string());
+        for ((class_id, token_id), claim) in claims {
+            CLAIMS.remove(
+                deps.storage,
+                (receiver.clone(), (class_id, token_id.clone())),
+            )?;
+            response = response.add_message(WasmMsg::Execute {
+                contract_addr: claim.nft_contract.to_string(),
                 msg: to_json_binary(&cw721_metadata_onchain::msg::ExecuteMsg::TransferNft {
                     recipient: recipient.to_string(),
-                    token_id: token_id.clone().into(),
+                    token_id: token_id.into(),
                 })?,
                 funds: vec![],
-            };
+            });
+        }
+        Ok(response)
+    }
 
-            response = response.add_message(transfer_msg);
+    fn execute_admin_set_history(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        config: Option<HistoryConfig>,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        let response = Response::default().add_attribute("method", "execute_admin_set_history");
+        match config {
+            Some(config) => {
+                HISTORY_CONFIG.save(deps.storage, &config)?;
+                Ok(response.add_attribute("retention", config.retention.to_string()))
+            }
+            None => {
+                HISTORY_CONFIG.remove(deps.storage);
+                Ok(response.add_attribute("history", "disabled"))
+            }
         }
+    }
 
+    fn execute_admin_set_voucher_flavour(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        code_id: u64,
+        flavour: Option<VoucherFlavour>,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        match &flavour {
+            Some(flavour) => CW721_CODE_FLAVOURS.save(deps.storage, code_id, flavour)?,
+            None => CW721_CODE_FLAVOURS.remove(deps.storage, code_id),
+        }
+        Ok(Response::default()
+            .add_attribute("method", "execute_admin_set_voucher_flavour")
+            .add_attribute("code_id", code_id.to_string())
+            .add_attribute("flavour", format!("{:?}", flavour.unwrap_or_default())))
+    }
+
+    fn execute_admin_set_unknown_metadata_policy(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        policy: Option<UnknownMetadataPolicy>,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        match &policy {
+            Some(policy) => UNKNOWN_METADATA_POLICY.save(deps.storage, policy)?,
+            None => UNKNOWN_METADATA_POLICY.remove(deps.storage),
+        }
+        Ok(Response::default()
+            .add_attribute("method", "execute_admin_set_unknown_metadata_policy")
+            .add_attribute("policy", format!("{:?}", policy.unwrap_or_default())))
+    }
+
+    fn execute_admin_set_cw721_code_id_override(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        target: Cw721CodeIdTarget,
+        code_id: Option<u64>,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        let (map, key, target_attr) = match target {
+            Cw721CodeIdTarget::Channel { channel_id } => {
+                (CHANNEL_CW721_CODE_IDS, channel_id, "channel_id")
+            }
+            Cw721CodeIdTarget::ClassIdPrefix { prefix } => {
+                if prefix.is_empty() {
+                    return Err(ContractError::EmptyClassIdPrefix {});
+                }
+                (CLASS_PREFIX_CW721_CODE_IDS, prefix, "class_id_prefix")
+            }
+        };
+        match code_id {
+            Some(code_id) => {
+                // make sure code ID exists, since instantiate2 address prediction depends on it
+                deps.querier.query_wasm_code_info(code_id)?;
+                map.save(deps.storage, key.clone(), &code_id)?
+            }
+            None => map.remove(deps.storage, key.clone()),
+        }
+        Ok(Response::default()
+            .add_attribute("method", "execute_admin_set_cw721_code_id_override")
+            .add_attribute(target_attr, key)
+            .add_attribute(
+                "code_id",
+                code_id.map_or_else(|| "none".to_string(), |code_id| code_id.to_string()),
+            ))
+    }
+
+    /// Sets the voucher profile of a class ID, applied on instantiation of the voucher collection.
+    /// In case the collection already exists and ICS721 is its creator, the profile is pushed to the collection.
+    fn execute_set_voucher_profile(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        class_id: String,
+        profile: Option<VoucherProfile>,
+    ) -> Result<Response<T>, ContractError> {
+        let class_id = ClassId::new(class_id);
+        // admin or collection owner on home chain can call this method
+        if assert_admin(deps.as_ref(), &env, &info.sender).is_err()
+            && !is_home_chain_owner(deps.as_ref(), &env, &class_id, &info.sender)?
+        {
+            return Err(ContractError::Unauthorized {});
+        }
+        let response = Response::default()
+            .add_attribute("method", "execute_set_voucher_profile")
+            .add_attribute("class_id", class_id.to_string());
+        let Some(profile) = profile else {
+            VOUCHER_PROFILES.remove(deps.storage, class_id);
+            return Ok(response.add_attribute("profile", "none"));
+        };
+        VOUCHER_PROFILES.save(deps.storage, class_id.clone(), &profile)?;
+
+        let Some(nft_contract) = query_nft_contract_for_class_id(deps.storage, class_id)? else {
+            return Ok(response.add_attribute("pushed", "false"));
+        };
+        // only creator can update collection info
+        if !is_voucher_creator(deps.as_ref(), &env, &nft_contract) {
+            return Ok(response.add_attribute("pushed", "false"));
+        }
+        Ok(response
+            .add_message(WasmMsg::Execute {
+                contract_addr: nft_contract.to_string(),
+                msg: self.voucher_profile_msg(&profile)?,
+                funds: vec![],
+            })
+            .add_attribute("pushed", "true"))
+    }
+
+    /// Sets the local royalty recipient of a voucher collection, proven by the collection owner on home chain.
+    /// In case the collection already exists and ICS721 is its creator, the royalty info is pushed to the collection.
+    fn execute_set_royalty_recipient(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        class_id: String,
+        payment_address: String,
+        proof: ClaimProof,
+    ) -> Result<Response<T>, ContractError> {
+        let class_id = ClassId::new(class_id);
+        let payment_address = deps.api.addr_validate(&payment_address)?;
+        let collection_data = CLASS_ID_TO_CLASS
+            .may_load(deps.storage, class_id.clone())?
+            .and_then(|class| class.data)
+            .and_then(|data| from_json::<CollectionData>(data).ok());
+        let owner = collection_data
+            .as_ref()
+            .and_then(|collection_data| collection_data.owner.clone())
+            .ok_or_else(|| ContractError::NoCollectionOwner(class_id.to_string()))?;
+        let nonce = ROYALTY_RECIPIENT_NONCES
+            .may_load(deps.storage, class_id.clone())?
+            .unwrap_or_default();
+        let msg = format!(
+            "royalty/{}/{}/{}/{}/{}",
+            env.block.chain_id, env.contract.address, class_id, payment_address, nonce
+        );
+        if !verify_owner_proof(deps.as_ref(), &info.sender, &owner, &msg, proof) {
+            return Err(ContractError::InvalidOwnerProof(owner));
+        }
+        ROYALTY_RECIPIENTS.save(deps.storage, class_id.clone(), &payment_address)?;
+        ROYALTY_RECIPIENT_NONCES.save(deps.storage, class_id.clone(), &(nonce + 1))?;
+
+        let response = Response::default()
+            .add_attribute("method", "execute_set_royalty_recipient")
+            .add_attribute("class_id", class_id.to_string())
+            .add_attribute("payment_address", payment_address.to_string());
+        // royalty info is only pushed, in case collection has royalties and ics721 is creator of the voucher collection
+        let share = collection_data
+            .and_then(|collection_data| collection_data.extension)
+            .and_then(|extension| extension.royalty_info)
+            .map(|royalty_info| royalty_info.share);
+        let nft_contract = query_nft_contract_for_class_id(deps.storage, class_id)?;
+        let (Some(share), Some(nft_contract)) = (share, nft_contract) else {
+            return Ok(response.add_attribute("pushed", "false"));
+        };
+        if !is_voucher_creator(deps.as_ref(), &env, &nft_contract) {
+            return Ok(response.add_attribute("pushed", "false"));
+        }
+        Ok(response
+            .add_message(WasmMsg::Execute {
+                contract_addr: nft_contract.to_string(),
+                msg: self.royalty_recipient_msg(&payment_address, share)?,
+                funds: vec![],
+            })
+            .add_attribute("pushed", "true"))
+    }
+
+    fn execute_register_voucher_contract(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        class_id: String,
+        contract: String,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        let contract = deps.api.addr_validate(&contract)?;
+        if CLASS_ID_AND_NFT_CONTRACT_INFO.has(deps.storage, &class_id) {
+            return Err(ContractError::ClassIdAlreadyRegistered(class_id));
+        }
+        if load_class_id_for_nft_contract(deps.storage, &contract)?.is_some() {
+            return Err(ContractError::NftContractAlreadyRegistered(
+                contract.to_string(),
+            ));
+        }
+        // vouchers are minted by ics721
+        let minter = query_minter(deps.as_ref(), contract.as_str())?;
+        if minter != Some(env.contract.address.to_string()) {
+            return Err(ContractError::NotMinter(contract.to_string()));
+        }
+
+        let class_id_info = ClassIdInfo {
+            class_id: ClassId::new(class_id.clone()),
+            address: contract.clone(),
+        };
+        CLASS_ID_AND_NFT_CONTRACT_INFO.save(deps.storage, &class_id, &class_id_info)?;
+
+        Ok(Response::default()
+            .add_attribute("method", "execute_register_voucher_contract")
+            .add_attribute("class_id", class_id)
+            .add_attribute("nft_contract", contract))
+    }
+
+    fn execute_admin_set_control_packets(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        channel_id: String,
+        enabled: bool,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        CONTROL_PACKET_CHANNELS.save(deps.storage, channel_id.clone(), &enabled)?;
+
+        Ok(Response::default()
+            .add_attribute("method", "execute_admin_set_control_packets")
+            .add_attribute("channel_id", channel_id)
+            .add_attribute("enabled", enabled.to_string()))
+    }
+
+    /// Sends a control packet for transferring creatorship of the voucher collection on the counterparty chain.
+    /// Class data is sent along, so the counterparty can check the sender is the collection owner.
+    #[allow(clippy::too_many_arguments)]
+    fn execute_transfer_creatorship(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        collection: String,
+        channel_id: String,
+        new_creator: Option<String>,
+        timeout: IbcTimeout,
+    ) -> Result<Response<T>, ContractError> {
+        if !control_packets_enabled(deps.storage, &channel_id)? {
+            return Err(ContractError::ControlPacketsDisabled(channel_id));
+        }
+        let collection = deps.api.addr_validate(&collection)?;
+        // only collections native to this chain, voucher class IDs differ from their collection address
+        if let Some(class_id) = load_class_id_for_nft_contract(deps.storage, &collection)? {
+            if class_id.to_string() != collection.to_string() {
+                return Err(ContractError::NotHomeCollection(collection.to_string()));
+            }
+        }
+        let class_data = self
+            .get_class_data(&deps, &collection)?
+            .map(|data| to_json_binary(&data))
+            .transpose()?
+            .ok_or_else(|| ContractError::NoCollectionOwner(collection.to_string()))?;
+        let owner = from_json::<CollectionData>(&class_data)
+            .ok()
+            .and_then(|collection_data| collection_data.owner)
+            .ok_or_else(|| ContractError::NoCollectionOwner(collection.to_string()))?;
+        if owner != info.sender.as_str() {
+            return Err(ContractError::NotCollectionOwner {
+                sender: info.sender.to_string(),
+                class_id: collection.to_string(),
+            });
+        }
+
+        let packet = Ics721ControlPacket::TransferCreatorship {
+            class_id: ClassId::new(collection.to_string()),
+            class_data,
+            sender: info.sender.to_string(),
+            new_creator: new_creator.clone(),
+        };
+        let ibc_msg = IbcMsg::SendPacket {
+            channel_id: channel_id.clone(),
+            data: to_json_binary(&packet)?,
+            timeout,
+        };
+
+        Ok(Response::default()
+            .add_message(ibc_msg)
+            .add_attribute("method", "execute_transfer_creatorship")
+            .add_attribute("collection", collection)
+            .add_attribute("channel_id", channel_id)
+            .add_attribute("sender", info.sender)
+            .add_attribute(
+                "new_creator",
+                new_creator.unwrap_or_else(|| "sender".to_string()),
+            ))
+    }
+
+    /// Migrates a page of voucher contracts, emitting an event for each contract.
+    /// The last class ID is returned for fetching the next page, in case of none all contracts are processed.
+    #[allow(clippy::too_many_arguments)]
+    fn execute_migrate_voucher_contracts(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        new_code_id: u64,
+        msg: Binary,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+        // make sure code ID exists, before migrating all contracts
+        deps.querier.query_wasm_code_info(new_code_id)?;
+
+        let limit = limit.unwrap_or(DEFAULT_MIGRATE_VOUCHER_CONTRACTS_LIMIT) as usize;
+        let nft_contracts = query_nft_contracts(
+            deps.as_ref(),
+            start_after.map(ClassId::new),
+            Some(limit as u32),
+        )?;
+        let last_class_id = match nft_contracts.len() {
+            len if len == limit => nft_contracts.last().map(|(class_id, _)| class_id.clone()),
+            _ => None,
+        };
+
+        let mut response = Response::default();
+        let (mut migrated, mut skipped) = (0u32, 0u32);
+        for (class_id, nft_contract) in nft_contracts {
+            // migration is only possible, in case ics721 is admin of the voucher contract
+            let is_admin = deps
+                .querier
+                .query_wasm_contract_info(nft_contract.to_string())
+                .map_or(false, |info| {
+                    info.admin == Some(env.contract.address.to_string())
+                });
+            let event = Event::new("ics721_migrate_voucher_contract")
+                .add_attribute("class_id", class_id)
+                .add_attribute("nft_contract", nft_contract.to_string());
+            if is_admin {
+                migrated += 1;
+                response = response
+                    .add_message(WasmMsg::Migrate {
+                        contract_addr: nft_contract.to_string(),
+                        new_code_id,
+                        msg: msg.clone(),
+                    })
+                    .add_event(event.add_attribute("status", "migrated"));
+            } else {
+                skipped += 1;
+                response = response.add_event(event.add_attribute("status", "skipped"));
+            }
+        }
+
+        Ok(response
+            .add_attribute("method", "execute_migrate_voucher_contracts")
+            .add_attribute("new_code_id", new_code_id.to_string())
+            .add_attribute("migrated", migrated.to_string())
+            .add_attribute("skipped", skipped.to_string())
+            .add_attribute(
+                "last_class_id",
+                last_class_id.unwrap_or_else(|| "none".to_string()),
+            ))
+    }
+
+    /// Rebuilds the channel index for a page of outgoing or incoming channel entries, stored before as plain maps.
+    /// The last class ID and token ID are returned for fetching the next page, in case of none all entries are processed.
+    fn execute_migrate_channel_indexes(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        incoming: bool,
+        start_after: Option<ClassToken>,
+        limit: Option<u32>,
+    ) -> Result<Response<T>, ContractError> {
+        // only admin can call this method
+        assert_admin(deps.as_ref(), &env, &info.sender)?;
+
+        let class_token_to_channel = if incoming {
+            &INCOMING_CLASS_TOKEN_TO_CHANNEL
+        } else {
+            &OUTGOING_CLASS_TOKEN_TO_CHANNEL
+        };
+        let limit = limit.unwrap_or(DEFAULT_MIGRATE_CHANNEL_INDEXES_LIMIT) as usize;
+        let start = start_after
+            .map(|class_token| Bound::exclusive((class_token.class_id, class_token.token_id)));
+        let entries = class_token_to_channel
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        for ((class_id, token_id), channel) in &entries {
+            // saving an existing entry (re-)creates its index entry
+            class_token_to_channel.save(
+                deps.storage,
+                (ClassId::new(class_id), TokenId::new(token_id)),
+                channel,
+            )?;
+        }
+        let (last_class_id, last_token_id) = match entries.last() {
+            Some(((class_id, token_id), _)) if entries.len() == limit => {
+                (class_id.clone(), token_id.clone())
+            }
+            _ => ("none".to_string(), "none".to_string()),
+        };
+
+        Ok(Response::default()
+            .add_attribute("method", "execute_migrate_channel_indexes")
+            .add_attribute("direction", if incoming { "incoming" } else { "outgoing" })
+            .add_attribute("migrated", entries.len().to_string())
+            .add_attribute("last_class_id", last_class_id)
+            .add_attribute("last_token_id", last_token_id))
+    }
+
+    /// Removes history records older than retention, oldest first.
+    /// In case history is disabled, all records are pruned.
+    fn execute_prune_history(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        limit: Option<u32>,
+    ) -> Result<Response<T>, ContractError> {
+        let retention = HISTORY_CONFIG
+            .may_load(deps.storage)?
+            .map(|config| config.retention);
+        // records are ordered by ID, hence by height
+        let all = HISTORY
+            .range(deps.storage, None, None, Order::Ascending)
+            .take_while(|item| match (item, retention) {
+                (Ok((_, record)), Some(retention)) => {
+                    record.height.saturating_add(retention) < env.block.height
+                }
+                _ => true,
+            })
+            .map(|item| item.map(|(id, _)| id));
+        let expired = match limit {
+            Some(limit) => all.take(limit as usize).collect::<StdResult<Vec<_>>>(),
+            None => all.collect::<StdResult<Vec<_>>>(),
+        }?;
+        for id in &expired {
+            HISTORY.remove(deps.storage, *id)?;
+        }
+        Ok(Response::default()
+            .add_attribute("method", "execute_prune_history")
+            .add_attribute("pruned", expired.len().to_string()))
+    }
+
+    /// Sends NFTs of expired claims back to their senders on source chain.
+    fn execute_return_expired_claims(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        start_after: Option<ClaimPosition>,
+        limit: Option<u32>,
+    ) -> Result<Response<T>, ContractError> {
+        let inbox_config = INBOX_CONFIG
+            .may_load(deps.storage)?
+            .ok_or(ContractError::InboxDisabled {})?;
+        let expired = load_expired_claims(deps.storage, env.block.time, start_after, limit)?;
+
+        let mut response =
+            Response::default().add_attribute("method", "execute_return_expired_claims");
+        for ((receiver, (class_id, token_id)), claim) in expired {
+            CLAIMS.remove(
+                deps.storage,
+                (receiver.clone(), (class_id.clone(), token_id.clone())),
+            )?;
+            // claim is restored, in case return fails or times out
+            RETURNED_CLAIMS.save(
+                deps.storage,
+                (class_id, token_id.clone()),
+                &(receiver, claim.clone()),
+            )?;
+            let Claim {
+                nft_contract,
+                sender,
+                channel_id,
+                ..
+            } = claim;
+            let msg = IbcOutgoingMsg {
+                receiver: sender,
+                channel_id,
+                timeout: IbcTimeout::with_timestamp(
+                    env.block.time.plus_seconds(inbox_config.return_timeout),
+                ),
+                memo: None,
+                refund_address: None,
+            };
+            let sub_response = self.receive_nft(
+                deps.branch(),
+                env.clone(),
+                &nft_contract,
+                token_id,
+                env.contract.address.to_string(),
+                to_json_binary(&msg)?,
+            )?;
+            response = response
+                .add_submessages(sub_response.messages)
+                .add_attributes(sub_response.attributes)
+                .add_events(sub_response.events);
+        }
         Ok(response)
     }
 
@@ -363,7 +1205,7 @@ where
 
     fn receive_nft(
         &self,
-        deps: DepsMut,
+        mut deps: DepsMut,
         env: Env,
         nft_contract: &Addr,
         token_id: TokenId,
@@ -372,6 +1214,11 @@ where
     ) -> Result<Response<T>, ContractError> {
         let nft_owner = deps.api.addr_validate(&nft_owner)?;
         let msg: IbcOutgoingMsg = from_json(msg)?;
+        let refund_address = msg
+            .refund_address
+            .as_ref()
+            .map(|addr| deps.api.addr_validate(addr))
+            .transpose()?;
 
         let class = match load_class_id_for_nft_contract(deps.as_ref().storage, nft_contract)? {
             Some(class_id) => CLASS_ID_TO_CLASS.load(deps.storage, class_id)?,
@@ -439,9 +1286,22 @@ where
             receiver: msg.receiver,
             memo: msg.memo,
         };
+        let before_send_messages =
+            self.before_send(deps.branch(), &env, &msg.channel_id, &ibc_message)?;
+        let data = to_json_binary(&ibc_message)?;
+        // in-flight packet is recorded in reply, once its sequence is known
+        let mut pending_packets = PENDING_OUTGOING_PACKETS
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        pending_packets.push(OutgoingPacket {
+            channel_id: msg.channel_id.clone(),
+            data: ibc_message,
+            voided: false,
+        });
+        PENDING_OUTGOING_PACKETS.save(deps.storage, &pending_packets)?;
         let ibc_message = IbcMsg::SendPacket {
             channel_id: msg.channel_id.clone(),
-            data: to_json_binary(&ibc_message)?,
+            data,
             timeout: msg.timeout,
         };
 
@@ -450,6 +1310,25 @@ where
             (class.id.clone(), token_id.clone()),
             &msg.channel_id,
         )?;
+        // vouchers sent back aren't escrowed, they are burned on ack success
+        let is_voucher =
+            INCOMING_CLASS_TOKEN_TO_CHANNEL.has(deps.storage, (class.id.clone(), token_id.clone()));
+        update_stats(deps.storage, &msg.channel_id, &class.id, |stats| {
+            stats.sent += 1;
+            if !is_voucher {
+                stats.escrowed += 1;
+            }
+        })?;
+        match &refund_address {
+            Some(refund_address) => OUTGOING_REFUND_ADDRESS.save(
+                deps.storage,
+                (class.id.clone(), token_id.clone()),
+                refund_address,
+            )?,
+            None => {
+                OUTGOING_REFUND_ADDRESS.remove(deps.storage, (class.id.clone(), token_id.clone()))
+            }
+        }
         // class_data might be collection data (if it comes from ICS721 contract) or some custom data (e.g. coming from nft-transfer module)
         // so only can output binary here
         let class_data_string = class
@@ -457,12 +1336,13 @@ where
             .map_or("none".to_string(), |data| format!("{data:?}"));
 
         Ok(Response::default()
+            .add_messages(before_send_messages)
             .add_attribute("method", "execute_receive_nft")
             .add_attribute("token_id", token_id)
             .add_attribute("class_id", class.id)
             .add_attribute("class_data", class_data_string)
             .add_attribute("channel_id", msg.channel_id)
-            .add_message(ibc_message))
+            .add_submessage(SubMsg::reply_on_success(ibc_message, SEND_PACKET_REPLY_ID)))
     }
 
     fn execute_pause(
@@ -502,9 +1382,15 @@ where
                 CallbackMsg::AddIncomingChannelEntries(entries) => {
                     self.callback_save_incoming_channel_entries(deps, entries)
                 }
+                CallbackMsg::AddClaims { receiver, claims } => {
+                    self.callback_add_claims(deps, receiver, claims)
+                }
                 CallbackMsg::Conjunction { operands } => {
                     Ok(Response::default().add_messages(operands))
                 }
+                CallbackMsg::HookMessages { messages } => {
+                    Ok(Response::default().add_messages(from_json::<Vec<CosmosMsg<T>>>(messages)?))
+                }
             }
         }
     }
@@ -519,7 +1405,12 @@ where
         receiver: String,
         create: VoucherCreation,
     ) -> Result<Response<T>, ContractError> {
-        let VoucherCreation { class, tokens } = create;
+        let VoucherCreation { mut class, tokens } = create;
+        let (class_data, collection_data, metadata_unknown) =
+            check_metadata(deps.storage, class.data.take(), |data| {
+                self.decode_class_data(data)
+            })?;
+        class.data = class_data;
 
         // Store mapping from classID to classURI. Notably, we don't check
         // if this has already been set. If a new NFT belonging to a class
@@ -539,7 +1430,7 @@ where
         };
 
         let (class_id_info, instantiate) =
-            self.create_instantiate_msg(deps, &env, class.clone())?;
+            self.create_instantiate_msg(deps, &env, class.clone(), collection_data)?;
 
         let token_ids = format!("{:?}", tokens);
         let event = Event::new("ics721_receive_create_vouchers")
@@ -549,6 +1440,7 @@ where
 
         Ok(Response::<T>::default()
             .add_attribute("method", "callback_create_vouchers")
+            .add_attribute("metadata_unknown", metadata_unknown.to_string())
             .add_event(event)
             .add_submessages(instantiate)
             .add_message(mint))
@@ -559,6 +1451,7 @@ where
         deps: DepsMut,
         env: &Env,
         class: Class,
+        collection_data: Option<CollectionData>,
     ) -> Result<(ClassIdInfo, Vec<SubMsg<T>>), ContractError> {
         let maybe_class_id_info =
             CLASS_ID_AND_NFT_CONTRACT_INFO.may_load(deps.as_ref().storage, &class.id)?;
@@ -566,7 +1459,7 @@ where
             Ok((nft_contract, vec![]))
         } else {
             let class_id = ClassId::new(class.id.clone());
-            let cw721_code_id = CW721_CODE_ID.load(deps.storage)?;
+            let cw721_code_id = voucher_cw721_code_id(deps.storage, &class.id)?;
             // for creating a predictable nft contract using, using instantiate2, we need: checksum, creator, and salt:
             // - using class id as salt for instantiating nft contract guarantees a) predictable address and b) uniqueness
             // for this salt must be of length 32 bytes, so we use sha256 to hash class id
@@ -589,11 +1482,30 @@ where
             CLASS_ID_AND_NFT_CONTRACT_INFO.save(deps.storage, &class.id, &class_id_info)?;
 
             let cw721_admin = CW721_ADMIN.load(deps.storage)?.map(|a| a.to_string());
+            let flavour = CW721_CODE_FLAVOURS
+                .may_load(deps.storage, cw721_code_id)?
+                .unwrap_or_default();
+            let msg = match legacy_voucher_init_msg(
+                deps.as_ref(),
+                env,
+                &class,
+                collection_data.as_ref(),
+                &flavour,
+            )? {
+                Some(msg) => msg,
+                None => self.init_msg(
+                    deps.as_ref(),
+                    env,
+                    &class,
+                    collection_data,
+                    cw721_admin.clone(),
+                )?,
+            };
             let message = SubMsg::<T>::reply_on_success(
                 WasmMsg::Instantiate2 {
-                    admin: cw721_admin.clone(),
+                    admin: cw721_admin,
                     code_id: cw721_code_id,
-                    msg: self.init_msg(deps.as_ref(), env, &class, cw721_admin)?,
+                    msg,
                     funds: vec![],
                     // Attempting to fit the class ID in the label field
                     // can make this field too long which causes data
@@ -607,12 +1519,26 @@ where
         }
     }
 
-    /// Default implementation using `cw721_base::msg::InstantiateMsg`
+    /// Decodes incoming class data, used for instantiating debt-voucher collections. Data failing to decode
+    /// is handled by `UnknownMetadataPolicy`.
+    fn decode_class_data(&self, data: &Binary) -> StdResult<CollectionData> {
+        from_json(data)
+    }
+
+    /// Decodes incoming token data, used for minting debt-vouchers. Data failing to decode is handled by
+    /// `UnknownMetadataPolicy`.
+    fn decode_token_data(&self, data: &Binary) -> StdResult<NftExtension> {
+        from_json(data)
+    }
+
+    /// Default implementation using `cw721_base::msg::InstantiateMsg`. Collection data is the class data
+    /// decoded by `decode_class_data`.
     fn init_msg(
         &self,
         deps: Deps,
         env: &Env,
         class: &Class,
+        collection_data: Option<CollectionData>,
         cw721_admin: Option<String>,
     ) -> StdResult<Binary> {
         // use ics721 creator for withdraw address
@@ -626,20 +1552,25 @@ where
             .or_else(|| admin.clone())
             .or_else(|| Some(creator.clone()))
             .unwrap();
+        // royalty recipient claimed by collection owner on home chain
+        let royalty_recipient = ROYALTY_RECIPIENTS
+            .may_load(deps.storage, class.id.clone())?
+            .map(|a| a.to_string());
         let mut instantiate_msg = cw721_metadata_onchain::msg::InstantiateMsg {
             name: class.id.clone().into(),
             symbol: class.id.clone().into(),
             collection_info_extension: None, // extension is set below, in case there's collection data
-            creator: Some(cw721_admin_or_ics721_admin_or_ics721_creator.clone()), // TODO maybe better using cw721 admin?
+            creator: Some(voucher_creator(
+                deps,
+                env,
+                &class.id,
+                cw721_admin_or_ics721_admin_or_ics721_creator.clone(),
+            )?),
             minter: Some(env.contract.address.to_string()),
             withdraw_address: Some(creator.clone()),
         };
 
         // use collection data for setting name and symbol
-        let collection_data = class
-            .data
-            .clone()
-            .and_then(|binary| from_json::<CollectionData>(binary).ok());
         if let Some(collection_data) = collection_data {
             instantiate_msg.name = collection_data.name;
             instantiate_msg.symbol = collection_data.symbol;
@@ -653,16 +1584,85 @@ where
                         explicit_content: ext.explicit_content,
                         start_trading_time: ext.start_trading_time,
                         royalty_info: ext.royalty_info.map(|r| RoyaltyInfoResponse {
-                            payment_address: cw721_admin_or_ics721_admin_or_ics721_creator, // r.payment_address cant be used, since it is from another chain
+                            // r.payment_address cant be used, since it is from another chain
+                            payment_address: royalty_recipient
+                                .unwrap_or(cw721_admin_or_ics721_admin_or_ics721_creator),
                             share: r.share,
                         }),
                     });
             instantiate_msg.collection_info_extension = collection_info_extension_msg;
         }
 
+        // voucher profile takes precedence over collection data
+        if let Some(profile) = VOUCHER_PROFILES.may_load(deps.storage, class.id.clone())? {
+            instantiate_msg.name = profile.name.unwrap_or(instantiate_msg.name);
+            instantiate_msg.symbol = profile.symbol.unwrap_or(instantiate_msg.symbol);
+            if profile.description.is_some()
+                || profile.image.is_some()
+                || profile.external_link.is_some()
+            {
+                let extension = instantiate_msg.collection_info_extension.get_or_insert(
+                    cw721::msg::CollectionExtensionMsg {
+                        description: None,
+                        image: None,
+                        external_link: None,
+                        explicit_content: None,
+                        start_trading_time: None,
+                        royalty_info: None,
+                    },
+                );
+                extension.description = profile.description.or(extension.description.take());
+                extension.image = profile.image.or(extension.image.take());
+                extension.external_link = profile.external_link.or(extension.external_link.take());
+            }
+        }
+
         to_json_binary(&instantiate_msg)
     }
 
+    /// Default implementation using `cw721_metadata_onchain::msg::ExecuteMsg::UpdateCollectionInfo`,
+    /// for pushing a royalty recipient to an existing voucher collection.
+    fn royalty_recipient_msg(&self, payment_address: &Addr, share: Decimal) -> StdResult<Binary> {
+        let msg = cw721_metadata_onchain::msg::ExecuteMsg::UpdateCollectionInfo {
+            collection_info: cw721::msg::CollectionInfoMsg {
+                name: None,
+                symbol: None,
+                extension: Some(cw721::msg::CollectionExtensionMsg {
+                    description: None,
+                    image: None,
+                    external_link: None,
+                    explicit_content: None,
+                    start_trading_time: None,
+                    royalty_info: Some(RoyaltyInfoResponse {
+                        payment_address: payment_address.to_string(),
+                        share,
+                    }),
+                }),
+            },
+        };
+        to_json_binary(&msg)
+    }
+
+    /// Default implementation using `cw721_metadata_onchain::msg::ExecuteMsg::UpdateCollectionInfo`,
+    /// for pushing a voucher profile to an existing voucher collection.
+    fn voucher_profile_msg(&self, profile: &VoucherProfile) -> StdResult<Binary> {
+        let msg = cw721_metadata_onchain::msg::ExecuteMsg::UpdateCollectionInfo {
+            collection_info: cw721::msg::CollectionInfoMsg {
+                name: profile.name.clone(),
+                symbol: profile.symbol.clone(),
+                extension: Some(cw721::msg::CollectionExtensionMsg {
+                    description: profile.description.clone(),
+                    image: profile.image.clone(),
+                    external_link: profile.external_link.clone(),
+                    explicit_content: None,
+                    start_trading_time: None,
+                    royalty_info: None,
+                }),
+            },
+        };
+        to_json_binary(&msg)
+    }
+
     /// Performs a recemption of debt vouchers returning the corresponding
     /// tokens to the receiver.
     fn callback_redeem_vouchers(
@@ -713,10 +1713,22 @@ where
         let receiver = deps.api.addr_validate(&receiver)?;
         let nft_contract =
             load_nft_contract_for_class_id(deps.as_ref().storage, class_id.to_string())?;
+        // vouchers are minted matching the flavour of the code ID the collection was instantiated with
+        let code_id = deps
+            .querier
+            .query_wasm_contract_info(nft_contract.to_string())?
+            .code_id;
+        let flavour = CW721_CODE_FLAVOURS
+            .may_load(deps.storage, code_id)?
+            .unwrap_or_default();
 
+        let mut metadata_unknown = false;
         let mint = tokens
             .into_iter()
             .map(|Token { id, uri, data }| {
+                let (data, extension, unknown) =
+                    check_metadata(deps.storage, data, |data| self.decode_token_data(data))?;
+                metadata_unknown |= unknown;
                 // Source chain may have provided token metadata, so we save token metadata here
                 // Note, once cw721 doesn't support on-chain metadata yet - but this is where we will set
                 // that value on the debt-voucher token once it is supported.
@@ -727,16 +1739,26 @@ where
                     &data,
                 )?;
 
+                let msg = match legacy_voucher_mint_msg(
+                    &flavour,
+                    id.clone().into(),
+                    uri.clone(),
+                    receiver.to_string(),
+                )? {
+                    Some(msg) => msg,
+                    None => self.mint_msg(id.into(), uri, receiver.to_string(), extension)?,
+                };
                 Ok(WasmMsg::Execute {
                     contract_addr: nft_contract.to_string(),
-                    msg: self.mint_msg(id.into(), uri, receiver.to_string(), data)?,
+                    msg,
                     funds: vec![],
                 })
             })
-            .collect::<StdResult<Vec<_>>>()?;
+            .collect::<Result<Vec<_>, ContractError>>()?;
 
         Ok(Response::default()
             .add_attribute("method", "callback_mint")
+            .add_attribute("metadata_unknown", metadata_unknown.to_string())
             .add_messages(mint))
     }
 
@@ -745,23 +1767,18 @@ where
         token_id: String,
         token_uri: Option<String>,
         owner: String,
-        data: Option<Binary>,
+        extension: Option<NftExtension>,
     ) -> StdResult<Binary> {
-        // parse token data and check whether it is of type NftExtension
-        let extension = data.and_then(|binary| {
-            from_json::<NftExtension>(binary)
-                .ok()
-                .map(|ext| NftExtensionMsg {
-                    animation_url: ext.animation_url,
-                    attributes: ext.attributes,
-                    background_color: ext.background_color,
-                    description: ext.description,
-                    external_url: ext.external_url,
-                    image: ext.image,
-                    image_data: ext.image_data,
-                    youtube_url: ext.youtube_url,
-                    name: ext.name,
-                })
+        let extension = extension.map(|ext| NftExtensionMsg {
+            animation_url: ext.animation_url,
+            attributes: ext.attributes,
+            background_color: ext.background_color,
+            description: ext.description,
+            external_url: ext.external_url,
+            image: ext.image,
+            image_data: ext.image_data,
+            youtube_url: ext.youtube_url,
+            name: ext.name,
         });
 
         let msg = cw721_metadata_onchain::msg::ExecuteMsg::Mint {
@@ -779,7 +1796,18 @@ where
         entries: Vec<(ClassId, TokenId)>,
     ) -> Result<Response<T>, ContractError> {
         for (class_id, token_id) in entries {
-            OUTGOING_CLASS_TOKEN_TO_CHANNEL.remove(deps.storage, (class_id, token_id));
+            let key = (class_id.clone(), token_id.clone());
+            if let Some(channel_id) =
+                OUTGOING_CLASS_TOKEN_TO_CHANNEL.may_load(deps.storage, key.clone())?
+            {
+                update_stats(deps.storage, &channel_id, &class_id, |stats| {
+                    stats.received += 1;
+                    stats.redeemed += 1;
+                    stats.escrowed = stats.escrowed.saturating_sub(1);
+                })?;
+            }
+            OUTGOING_CLASS_TOKEN_TO_CHANNEL.remove(deps.storage, key.clone())?;
+            OUTGOING_REFUND_ADDRESS.remove(deps.storage, key);
         }
         Ok(Response::default().add_attribute("method", "callback_redeem_outgoing_channel_entries"))
     }
@@ -790,11 +1818,29 @@ where
         entries: Vec<((ClassId, TokenId), String)>,
     ) -> Result<Response<T>, ContractError> {
         for (key, channel) in entries {
+            update_stats(deps.storage, &channel, &key.0, |stats| {
+                stats.received += 1;
+                stats.vouchered += 1;
+            })?;
             INCOMING_CLASS_TOKEN_TO_CHANNEL.save(deps.storage, key, &channel)?;
         }
         Ok(Response::default().add_attribute("method", "callback_save_incoming_channel_entries"))
     }
 
+    fn callback_add_claims(
+        &self,
+        deps: DepsMut,
+        receiver: String,
+        claims: Vec<((ClassId, TokenId), Claim)>,
+    ) -> Result<Response<T>, ContractError> {
+        for (key, claim) in claims {
+            CLAIMS.save(deps.storage, (receiver.clone(), key), &claim)?;
+        }
+        Ok(Response::default()
+            .add_attribute("method", "callback_add_claims")
+            .add_attribute("receiver", receiver))
+    }
+
     fn migrate(
         &self,
         deps: DepsMut,
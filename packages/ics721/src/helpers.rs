@@ -1,18 +1,39 @@
 use cosmwasm_std::{
-    from_json, instantiate2_address, to_json_binary, Addr, Binary, CodeInfoResponse, Deps,
-    IbcPacket, StdResult, Storage, SubMsg, WasmMsg,
+    from_json, instantiate2_address, to_json_binary, Addr, Binary, CodeInfoResponse,
+    ContractInfoResponse, Deps, Env, Event, IbcPacket, Order, StdResult, Storage, SubMsg,
+    Timestamp, WasmMsg,
 };
+use cw_ownable::Ownership;
+use cw_storage_plus::Bound;
+use ripemd::Ripemd160;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 
 use crate::{
     ibc::ACK_CALLBACK_REPLY_ID,
-    state::{CONTRACT_ADDR_LENGTH, INCOMING_PROXY},
+    msg::{
+        AdminCleanAction, AdminCleanAndBurnEntry, AdminCleanAndUnescrowEntry, ClaimPosition,
+        ClaimProof, NameServiceQueryMsg,
+    },
+    query::query_nft_contract_for_class_id,
+    state::{
+        Claim, CollectionData, HistoryAction, HistoryOutcome, HistoryRecord, TransferStats,
+        UniversalAllNftInfoResponse, UnknownMetadataPolicy, CHANNEL_CW721_CODE_IDS, CHANNEL_STATS,
+        CLAIMS, CLASS_ID_TO_CLASS, CLASS_PREFIX_CW721_CODE_IDS, CLASS_STATS, CONTRACT_ADDR_LENGTH,
+        CONTROL_PACKET_CHANNELS, CONVERT_RECEIVER_PREFIX, CW721_CODE_ID, HISTORY, HISTORY_CONFIG,
+        HISTORY_NEXT_ID, IBC_RECEIVE_TOKEN_METADATA, INCOMING_CLASS_TOKEN_TO_CHANNEL,
+        INCOMING_PROXY, NAME_SERVICE, OUTGOING_CLASS_TOKEN_TO_CHANNEL, OUTGOING_REFUND_ADDRESS,
+        RETURNED_CLAIMS, SEND_NFT_TO_CONTRACTS, UNKNOWN_METADATA_POLICY,
+    },
+    utils::convert_owner_chain_address,
     ContractError,
 };
 use ics721_types::{
     ibc_types::NonFungibleTokenPacketData,
+    token_types::{ClassId, TokenId},
     types::{
-        Ics721AckCallbackMsg, Ics721Callbacks, Ics721Memo, Ics721ReceiveCallbackMsg, Ics721Status,
+        Adr8Memo, Ics721AckCallbackMsg, Ics721AckResult, Ics721Callbacks, Ics721Memo,
+        Ics721ReceiveCallbackMsg, Ics721SendNftMemo, Ics721Status, Ics721WasmHook, Ics721WasmMemo,
         ReceiverExecuteMsg,
     },
 };
@@ -24,9 +45,17 @@ fn parse_memo<T: for<'de> Deserialize<'de>>(memo: Option<String>) -> Option<T> {
     from_json::<T>(&binary).ok()
 }
 
-/// Parse callback from the memo field
+/// Parse ADR-8 memo, which - unlike `Ics721Memo` - is plain JSON
+fn parse_adr8_memo(memo: Option<String>) -> Option<Adr8Memo> {
+    from_json::<Adr8Memo>(memo?.as_bytes()).ok()
+}
+
+/// Parse callback from the memo field, either in `Ics721Memo` or ADR-8 format
 fn parse_callback(memo: Option<String>) -> Option<Ics721Callbacks> {
-    parse_memo::<Ics721Memo>(memo)?.callbacks
+    match parse_memo::<Ics721Memo>(memo.clone()) {
+        Some(ics721_memo) => ics721_memo.callbacks,
+        None => parse_adr8_memo(memo).map(Ics721Callbacks::from),
+    }
 }
 
 // Create a subMsg that execute the callback on the sender callback
@@ -35,17 +64,27 @@ fn parse_callback(memo: Option<String>) -> Option<Ics721Callbacks> {
 // if we were to fail the whole tx, the NFT would have been minted on
 // the other chain while the NFT on this chain would not have been
 // burned
-pub(crate) fn ack_callback_msg(
+pub(crate) fn ack_callback_msg<T>(
     deps: Deps,
     status: Ics721Status,
     packet: NonFungibleTokenPacketData,
     nft_contract: String,
-) -> Option<SubMsg> {
+    ack_result: Option<Ics721AckResult>,
+) -> Option<SubMsg<T>> {
     // Get the callback object
-    let callbacks = parse_callback(packet.memo.clone())?;
+    let packet_memo = packet.memo.clone();
+    let callbacks = parse_callback(packet_memo.clone())?;
+
+    // On timeout, the dedicated timeout callback takes precedence over the ack callback
+    let (callback_data, callback_addr) = match (&status, callbacks.timeout_callback_data) {
+        (Ics721Status::Timeout, Some(timeout_callback_data)) => {
+            (Some(timeout_callback_data), callbacks.timeout_callback_addr)
+        }
+        _ => (callbacks.ack_callback_data, callbacks.ack_callback_addr),
+    };
 
     // Validate the address
-    let receiver = callbacks.ack_callback_addr.unwrap_or(packet.sender.clone());
+    let receiver = callback_addr.unwrap_or(packet.sender.clone());
     let contract_addr = deps.api.addr_validate(receiver.as_str()).ok()?.to_string();
 
     // Create the message we send to the contract
@@ -56,19 +95,28 @@ pub(crate) fn ack_callback_msg(
             status,
             nft_contract,
             original_packet: packet,
-            msg: callbacks.ack_callback_data?,
+            msg: callback_data?,
+            ack_result,
         },
     ))
     .ok()?;
 
-    Some(SubMsg::reply_on_error(
+    let sub_msg = SubMsg::reply_on_error(
         WasmMsg::Execute {
             contract_addr,
             msg,
             funds: vec![],
         },
         ACK_CALLBACK_REPLY_ID,
-    ))
+    );
+    // ADR-8 callbacks may limit gas on source side
+    match parse_adr8_memo(packet_memo)
+        .and_then(|memo| memo.src_callback)
+        .and_then(|callback| callback.gas_limit)
+    {
+        Some(gas_limit) => Some(sub_msg.with_gas_limit(gas_limit.u64())),
+        None => Some(sub_msg),
+    }
 }
 
 /// If there is an incoming proxy, let proxy validate the packet, in case it fails, we fail the transfer
@@ -138,6 +186,559 @@ pub(crate) fn generate_receive_callback_msg(
     })
 }
 
+/// Get the ibc-hooks style wasm hook from the memo field (plain JSON)
+/// if there is no wasm hook returns None
+pub(crate) fn get_wasm_hook(packet: &NonFungibleTokenPacketData) -> Option<Ics721WasmHook> {
+    from_json::<Ics721WasmMemo>(packet.memo.as_ref()?.as_bytes())
+        .ok()?
+        .wasm
+}
+
+/// Get the delivery via `SendNft` to the receiver, either opted-in by `send_nft` memo
+/// or auto-detected (if enabled) in case receiver is a contract
+pub(crate) fn get_send_nft_hook(
+    deps: Deps,
+    packet: &NonFungibleTokenPacketData,
+) -> StdResult<Option<Ics721WasmHook>> {
+    let send_nft = packet
+        .memo
+        .as_ref()
+        .and_then(|memo| from_json::<Ics721SendNftMemo>(memo.as_bytes()).ok())
+        .and_then(|memo| memo.send_nft);
+    let msg = match send_nft {
+        Some(send_nft) => send_nft.msg.unwrap_or_default(),
+        None => {
+            let send_nft_to_contracts = SEND_NFT_TO_CONTRACTS
+                .may_load(deps.storage)?
+                .unwrap_or(false);
+            // contract info is only available for contracts
+            if !send_nft_to_contracts
+                || deps
+                    .querier
+                    .query_wasm_contract_info(packet.receiver.clone())
+                    .is_err()
+            {
+                return Ok(None);
+            }
+            Binary::default()
+        }
+    };
+    Ok(Some(Ics721WasmHook {
+        contract: packet.receiver.clone(),
+        msg,
+    }))
+}
+
+/// Messages for sending NFTs escrowed by ics721 to a contract, using cw721's `SendNft`
+pub(crate) fn send_nft_msgs(
+    nft_contract: &Addr,
+    token_ids: &[TokenId],
+    contract: String,
+    msg: Binary,
+) -> StdResult<Vec<WasmMsg>> {
+    token_ids
+        .iter()
+        .map(|token_id| {
+            Ok(WasmMsg::Execute {
+                contract_addr: nft_contract.to_string(),
+                msg: to_json_binary(&cw721_metadata_onchain::msg::ExecuteMsg::SendNft {
+                    contract: contract.clone(),
+                    token_id: token_id.clone().into(),
+                    msg: msg.clone(),
+                })?,
+                funds: vec![],
+            })
+        })
+        .collect()
+}
+
+/// Validates receiver of an incoming packet:
+/// - in case a name service is set, a receiver being a name (e.g. `alice.stars`) is resolved to its address.
+/// - in case conversion is enabled for the local channel, a receiver with a foreign bech32 prefix (e.g. `juno1…`)
+///   is converted to the local prefix (e.g. `stars1…`), as long as its data length is valid
+///   (20 bytes for accounts, 32 bytes for contracts).
+pub(crate) fn resolve_receiver(
+    deps: Deps,
+    env: &Env,
+    channel_id: &str,
+    receiver: &str,
+) -> Result<Addr, ContractError> {
+    // bech32 addresses never contain a dot
+    if receiver.contains('.') {
+        if let Some(name_service) = NAME_SERVICE.may_load(deps.storage)? {
+            let address: String = deps
+                .querier
+                .query_wasm_smart(
+                    &name_service,
+                    &NameServiceQueryMsg::AssociatedAddress {
+                        name: receiver.to_string(),
+                    },
+                )
+                .map_err(|_| ContractError::NameNotResolved {
+                    name: receiver.to_string(),
+                    name_service: name_service.to_string(),
+                })?;
+            return Ok(deps.api.addr_validate(&address)?);
+        }
+    }
+    let err = match deps.api.addr_validate(receiver) {
+        Ok(receiver) => return Ok(receiver),
+        Err(err) => err,
+    };
+    let convert = CONVERT_RECEIVER_PREFIX
+        .may_load(deps.storage, channel_id.to_string())?
+        .unwrap_or(false);
+    if !convert {
+        return Err(err.into());
+    }
+    match bech32::decode(receiver) {
+        Ok((_, data)) if data.len() == 20 || data.len() == 32 => {
+            let converted = convert_owner_chain_address(env, receiver)?;
+            Ok(deps.api.addr_validate(&converted)?)
+        }
+        _ => Err(err.into()),
+    }
+}
+
+/// Checks given class id and collection matches stored nft contract, returns the burn message in case NFT exists.
+/// NB: burn requires approval from owner, or owner burns it himself.
+pub(crate) fn plan_clean_and_burn(
+    deps: Deps,
+    entry: &AdminCleanAndBurnEntry,
+) -> Result<Option<WasmMsg>, ContractError> {
+    let child_collection =
+        assert_nft_contract(deps, &entry.collection, &entry.class_id, &entry.token_id)?;
+    // check NFT on child collection owned by owner
+    match query_nft_owner(deps, &child_collection, &entry.token_id) {
+        Some(owner) => {
+            if owner != entry.owner {
+                return Err(ContractError::NotOwnerOfNft {
+                    recipient: entry.owner.clone(),
+                    token_id: entry.token_id.clone(),
+                    owner,
+                });
+            }
+            Ok(Some(WasmMsg::Execute {
+                contract_addr: child_collection.to_string(),
+                msg: to_json_binary(&cw721_metadata_onchain::msg::ExecuteMsg::Burn {
+                    token_id: entry.token_id.clone(),
+                })?,
+                funds: vec![],
+            }))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Checks given class id and collection matches stored nft contract, returns the transfer message in case NFT exists.
+pub(crate) fn plan_clean_and_unescrow(
+    deps: Deps,
+    env: &Env,
+    entry: &AdminCleanAndUnescrowEntry,
+) -> Result<Option<WasmMsg>, ContractError> {
+    let home_collection =
+        assert_nft_contract(deps, &entry.collection, &entry.class_id, &entry.token_id)?;
+    // check NFT on home collection owned by ics721 contract
+    match query_nft_owner(deps, &home_collection, &entry.token_id) {
+        Some(owner) => {
+            if owner != env.contract.address {
+                return Err(ContractError::NotEscrowedByIcs721(owner));
+            }
+            Ok(Some(WasmMsg::Execute {
+                contract_addr: home_collection.to_string(),
+                msg: to_json_binary(&cw721_metadata_onchain::msg::ExecuteMsg::TransferNft {
+                    recipient: entry.recipient.clone(),
+                    token_id: entry.token_id.clone(),
+                })?,
+                funds: vec![],
+            }))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Removes incoming channel entry and metadata.
+pub(crate) fn clean_incoming(
+    storage: &mut dyn Storage,
+    class_id: &str,
+    token_id: &str,
+) -> StdResult<()> {
+    let key = (ClassId::new(class_id), TokenId::new(token_id));
+    if let Some(channel_id) = INCOMING_CLASS_TOKEN_TO_CHANNEL.may_load(storage, key.clone())? {
+        update_stats(storage, &channel_id, &key.0, |stats| {
+            stats.vouchered = stats.vouchered.saturating_sub(1)
+        })?;
+    }
+    INCOMING_CLASS_TOKEN_TO_CHANNEL.remove(storage, key.clone())?;
+    IBC_RECEIVE_TOKEN_METADATA.remove(storage, key);
+    Ok(())
+}
+
+/// Removes outgoing channel entry.
+pub(crate) fn clean_outgoing(
+    storage: &mut dyn Storage,
+    class_id: &str,
+    token_id: &str,
+) -> StdResult<()> {
+    let key = (ClassId::new(class_id), TokenId::new(token_id));
+    if let Some(channel_id) = OUTGOING_CLASS_TOKEN_TO_CHANNEL.may_load(storage, key.clone())? {
+        // vouchers aren't escrowed
+        if !INCOMING_CLASS_TOKEN_TO_CHANNEL.has(storage, key.clone()) {
+            update_stats(storage, &channel_id, &key.0, |stats| {
+                stats.escrowed = stats.escrowed.saturating_sub(1)
+            })?;
+        }
+    }
+    OUTGOING_CLASS_TOKEN_TO_CHANNEL.remove(storage, key.clone())?;
+    OUTGOING_REFUND_ADDRESS.remove(storage, key.clone());
+    RETURNED_CLAIMS.remove(storage, key);
+    Ok(())
+}
+
+/// Updates transfer stats of the given local channel and class ID.
+pub(crate) fn update_stats(
+    storage: &mut dyn Storage,
+    channel_id: &str,
+    class_id: &ClassId,
+    update: impl Fn(&mut TransferStats),
+) -> StdResult<()> {
+    CHANNEL_STATS.update(storage, channel_id.to_string(), |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        update(&mut stats);
+        Ok(stats)
+    })?;
+    CLASS_STATS.update(storage, class_id.clone(), |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        update(&mut stats);
+        Ok(stats)
+    })?;
+    Ok(())
+}
+
+/// Code ID for instantiating the debt-voucher collection of a local class ID (e.g. `wasm.stars1…/channel-3/juno1…`):
+/// the longest matching class ID prefix override, or else the override of the local channel, or else `CW721_CODE_ID`.
+/// Prefixes match at `/` boundaries only: the class ID itself, or any of its prefixes ending with `/`.
+pub(crate) fn voucher_cw721_code_id(storage: &dyn Storage, class_id: &str) -> StdResult<u64> {
+    // one lookup per prefix, instead of ranging over all overrides
+    let prefixes = std::iter::once(class_id).chain(
+        class_id
+            .rmatch_indices('/')
+            .map(|(index, _)| &class_id[..=index]),
+    );
+    for prefix in prefixes {
+        if let Some(code_id) = CLASS_PREFIX_CW721_CODE_IDS.may_load(storage, prefix.to_string())? {
+            return Ok(code_id);
+        }
+    }
+    if let Some(channel_id) = voucher_channel_id(class_id) {
+        if let Some(code_id) = CHANNEL_CW721_CODE_IDS.may_load(storage, channel_id.to_string())? {
+            return Ok(code_id);
+        }
+    }
+    CW721_CODE_ID.load(storage)
+}
+
+/// Local channel of a voucher class ID, which is `{port}/{channel}/{class ID on counterparty chain}`.
+fn voucher_channel_id(class_id: &str) -> Option<&str> {
+    class_id.splitn(3, '/').nth(1)
+}
+
+/// Creator of the debt-voucher collection of a local class ID. ICS721 is creator, in case control packets are
+/// enabled on the voucher's channel, so creatorship can be transferred by the collection owner on home chain.
+/// Otherwise the given default creator is used.
+pub fn voucher_creator(
+    deps: Deps,
+    env: &Env,
+    class_id: &str,
+    default_creator: String,
+) -> StdResult<String> {
+    match voucher_channel_id(class_id) {
+        Some(channel_id) if control_packets_enabled(deps.storage, channel_id)? => {
+            Ok(env.contract.address.to_string())
+        }
+        _ => Ok(default_creator),
+    }
+}
+
+/// Adds history records, in case transfer history is enabled.
+pub(crate) fn add_history(
+    storage: &mut dyn Storage,
+    records: impl IntoIterator<Item = HistoryRecord>,
+) -> StdResult<()> {
+    if !HISTORY_CONFIG.exists(storage) {
+        return Ok(());
+    }
+    let mut id = HISTORY_NEXT_ID.may_load(storage)?.unwrap_or_default();
+    for record in records {
+        HISTORY.save(storage, id, &record)?;
+        id += 1;
+    }
+    HISTORY_NEXT_ID.save(storage, &id)
+}
+
+/// History records for all NFTs of an outgoing packet, sender being the local address.
+pub(crate) fn outgoing_history(
+    action: HistoryAction,
+    data: &NonFungibleTokenPacketData,
+    channel_id: &str,
+    sequence: Option<u64>,
+    height: u64,
+    outcome: HistoryOutcome,
+) -> Vec<HistoryRecord> {
+    data.token_ids
+        .iter()
+        .map(|token_id| HistoryRecord {
+            action: action.clone(),
+            class_id: data.class_id.clone(),
+            token_id: token_id.clone(),
+            channel_id: Some(channel_id.to_string()),
+            sequence,
+            address: data.sender.clone(),
+            counterparty: Some(data.receiver.clone()),
+            height,
+            outcome: outcome.clone(),
+        })
+        .collect()
+}
+
+/// History record of an admin clean-up, in case NFT is burned or transferred.
+pub(crate) fn admin_clean_history(
+    class_id: &str,
+    token_id: &str,
+    action: &AdminCleanAction,
+    height: u64,
+) -> Option<HistoryRecord> {
+    let (action, address) = match action {
+        AdminCleanAction::Burn { owner } => (HistoryAction::AdminBurn, owner),
+        AdminCleanAction::Transfer { recipient } => (HistoryAction::AdminTransfer, recipient),
+        _ => return None,
+    };
+    Some(HistoryRecord {
+        action,
+        class_id: ClassId::new(class_id),
+        token_id: TokenId::new(token_id),
+        channel_id: None,
+        sequence: None,
+        address: address.clone(),
+        counterparty: None,
+        height,
+        outcome: HistoryOutcome::Success,
+    })
+}
+
+pub(crate) fn admin_clean_event(
+    class_id: &str,
+    token_id: &str,
+    action: &AdminCleanAction,
+) -> Event {
+    let event = Event::new("ics721_admin_clean")
+        .add_attribute("class_id", class_id)
+        .add_attribute("token_id", token_id);
+    match action {
+        AdminCleanAction::Burn { owner } => event
+            .add_attribute("action", "burn")
+            .add_attribute("owner", owner),
+        AdminCleanAction::Transfer { recipient } => event
+            .add_attribute("action", "transfer")
+            .add_attribute("recipient", recipient),
+        AdminCleanAction::CleanStateOnly {} => event.add_attribute("action", "clean_state_only"),
+        AdminCleanAction::Failed { error } => event
+            .add_attribute("action", "failed")
+            .add_attribute("error", error),
+    }
+}
+
+/// Checks given collection is the same as stored in the contract for the class id.
+fn assert_nft_contract(
+    deps: Deps,
+    collection: &str,
+    class_id: &str,
+    token_id: &str,
+) -> Result<Addr, ContractError> {
+    let collection = deps.api.addr_validate(collection)?;
+    match query_nft_contract_for_class_id(deps.storage, ClassId::new(class_id))? {
+        Some(cw721_addr) => {
+            if cw721_addr != collection {
+                return Err(ContractError::NoNftContractMatch {
+                    child_collection: collection.to_string(),
+                    class_id: class_id.to_string(),
+                    token_id: token_id.to_string(),
+                    cw721_addr: cw721_addr.to_string(),
+                });
+            }
+            Ok(collection)
+        }
+        None => Err(ContractError::NoNftContractForClassId(class_id.to_string())),
+    }
+}
+
+/// Owner of NFT, in case it exists.
+pub(crate) fn query_nft_owner(deps: Deps, collection: &Addr, token_id: &str) -> Option<String> {
+    deps.querier
+        .query_wasm_smart::<UniversalAllNftInfoResponse>(
+            collection,
+            &cw721_metadata_onchain::msg::QueryMsg::AllNftInfo {
+                token_id: token_id.to_string(),
+                include_expired: None,
+            },
+        )
+        .ok()
+        .map(|info| info.access.owner.to_string())
+}
+
+/// Whether sender is the collection owner on its home chain, as given in class data.
+/// The owner address is converted to the local bech32 prefix.
+pub(crate) fn is_home_chain_owner(
+    deps: Deps,
+    env: &Env,
+    class_id: &ClassId,
+    sender: &Addr,
+) -> StdResult<bool> {
+    let owner = CLASS_ID_TO_CLASS
+        .may_load(deps.storage, class_id.clone())?
+        .and_then(|class| class.data)
+        .and_then(|data| from_json::<CollectionData>(data).ok())
+        .and_then(|collection_data| collection_data.owner);
+    Ok(owner.map_or(false, |owner| {
+        convert_owner_chain_address(env, &owner).map_or(false, |owner| owner == sender.as_str())
+    }))
+}
+
+/// Whether control packets are enabled on the given local channel, by default they are disabled.
+pub(crate) fn control_packets_enabled(storage: &dyn Storage, channel_id: &str) -> StdResult<bool> {
+    Ok(CONTROL_PACKET_CHANNELS
+        .may_load(storage, channel_id.to_string())?
+        .unwrap_or(false))
+}
+
+/// Whether ICS721 is creator of the voucher collection, hence eligible to update its collection info.
+pub(crate) fn is_voucher_creator(deps: Deps, env: &Env, nft_contract: &Addr) -> bool {
+    deps.querier
+        .query_wasm_smart::<Ownership<Addr>>(
+            nft_contract,
+            &cw721_metadata_onchain::msg::QueryMsg::GetCreatorOwnership {},
+        )
+        .map_or(false, |ownership| {
+            ownership.owner == Some(env.contract.address.clone())
+        })
+}
+
+/// Checks incoming class or token data against the unknown metadata policy, in case it fails to decode.
+/// Returns the data to be stored, the decoded data, and whether the data is in an unknown format.
+#[allow(clippy::type_complexity)]
+pub(crate) fn check_metadata<D>(
+    storage: &dyn Storage,
+    data: Option<Binary>,
+    decode: impl Fn(&Binary) -> StdResult<D>,
+) -> Result<(Option<Binary>, Option<D>, bool), ContractError> {
+    let Some(data) = data else {
+        return Ok((None, None, false));
+    };
+    let err = match decode(&data) {
+        Ok(decoded) => return Ok((Some(data), Some(decoded), false)),
+        Err(err) => err,
+    };
+    match UNKNOWN_METADATA_POLICY
+        .may_load(storage)?
+        .unwrap_or_default()
+    {
+        UnknownMetadataPolicy::Ignore => Ok((None, None, true)),
+        UnknownMetadataPolicy::StoreRaw => Ok((Some(data), None, true)),
+        UnknownMetadataPolicy::Reject => Err(ContractError::UnknownMetadataFormat(err.to_string())),
+    }
+}
+
+/// Loads claims expired at the given time, ordered by expiration. Refund claims never expire.
+pub(crate) fn load_expired_claims(
+    storage: &dyn Storage,
+    time: Timestamp,
+    start_after: Option<ClaimPosition>,
+    limit: Option<u32>,
+) -> StdResult<Vec<((String, (ClassId, TokenId)), Claim)>> {
+    let start = start_after.map(|position| {
+        Bound::exclusive((
+            position.expiration.nanos(),
+            (position.receiver, (position.class_id, position.token_id)),
+        ))
+    });
+    // lowest possible key of the next nanosecond, so claims expiring at given time are included
+    let end = Bound::exclusive((
+        time.nanos() + 1,
+        (String::new(), (ClassId::new(""), TokenId::new(""))),
+    ));
+    let all = CLAIMS
+        .idx
+        .expiration
+        .range(storage, start, Some(end), Order::Ascending);
+    match limit {
+        Some(limit) => all.take(limit as usize).collect(),
+        None => all.collect(),
+    }
+}
+
+/// Only the (wasm) admin of this contract may call admin messages. In case there is no admin, anyone can call.
+pub(crate) fn assert_admin(deps: Deps, env: &Env, sender: &Addr) -> Result<(), ContractError> {
+    let ContractInfoResponse { admin, .. } = deps
+        .querier
+        .query_wasm_contract_info(env.contract.address.to_string())?;
+    if admin.is_some() && sender != admin.unwrap() {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Verifies sender owns the raw receiver. Receiver's bech32 data must either match:
+/// - the sender's address data, or
+/// - the address data derived from the signer's public key: `ripemd160(sha256(pub_key))`.
+pub(crate) fn verify_claim_proof(
+    deps: Deps,
+    env: &Env,
+    sender: &Addr,
+    receiver: &str,
+    recipient: &str,
+    proof: ClaimProof,
+) -> Result<(), ContractError> {
+    let msg = format!(
+        "{}/{}/{}/{}",
+        env.block.chain_id, env.contract.address, receiver, recipient
+    );
+    if !verify_owner_proof(deps, sender, receiver, &msg, proof) {
+        return Err(ContractError::InvalidClaimProof(receiver.to_string()));
+    }
+    Ok(())
+}
+
+/// Whether the sender holds the same bech32 data as owner, or owner's key signed the message.
+pub(crate) fn verify_owner_proof(
+    deps: Deps,
+    sender: &Addr,
+    owner: &str,
+    msg: &str,
+    proof: ClaimProof,
+) -> bool {
+    let Ok((_hrp, owner_data)) = bech32::decode(owner) else {
+        return false;
+    };
+    let proven_data = match proof {
+        ClaimProof::Bech32 {} => match deps.api.addr_canonicalize(sender.as_str()) {
+            Ok(sender) => sender.as_slice().to_vec(),
+            Err(_) => return false,
+        },
+        ClaimProof::Signature { pub_key, signature } => {
+            let msg_hash = Sha256::digest(msg.as_bytes());
+            let verified = deps
+                .api
+                .secp256k1_verify(&msg_hash, &signature, &pub_key)
+                .unwrap_or(false);
+            if !verified {
+                return false;
+            }
+            Ripemd160::digest(Sha256::digest(pub_key.as_slice())).to_vec()
+        }
+    };
+    owner_data == proven_data
+}
+
 pub fn get_instantiate2_address(
     deps: Deps,
     creator: &str,
@@ -161,11 +762,250 @@ pub fn get_instantiate2_address(
     }
 }
 
+#[cfg(test)]
 mod test {
+    use cosmwasm_std::{
+        testing::mock_dependencies, to_json_binary, Binary, CosmosMsg, Empty, WasmMsg,
+    };
+    use ics721_types::{
+        ibc_types::NonFungibleTokenPacketData,
+        token_types::{ClassId, TokenId},
+        types::{Ics721Callbacks, Ics721Memo, Ics721Status},
+    };
+
     #[test]
     fn test_parsing() {
         let memo = Some("some".to_string());
         let callbacks = super::parse_callback(memo);
         println!("{callbacks:?}")
     }
+
+    #[test]
+    fn test_timeout_callback() {
+        let deps = mock_dependencies();
+        let callbacks = Ics721Callbacks {
+            ack_callback_data: Some(to_json_binary("ack").unwrap()),
+            ack_callback_addr: Some("ackaddr".to_string()),
+            receive_callback_data: None,
+            receive_callback_addr: None,
+            timeout_callback_data: Some(to_json_binary("timeout").unwrap()),
+            timeout_callback_addr: Some("timeoutaddr".to_string()),
+        };
+        let memo = Ics721Memo {
+            callbacks: Some(callbacks),
+        };
+        let packet = NonFungibleTokenPacketData {
+            class_id: ClassId::new("id"),
+            class_uri: None,
+            class_data: None,
+            token_ids: vec![TokenId::new("1")],
+            token_uris: None,
+            token_data: None,
+            sender: "sender".to_string(),
+            receiver: "receiver".to_string(),
+            memo: Some(Binary::to_base64(&to_json_binary(&memo).unwrap())),
+        };
+        let callback_addr = |status: Ics721Status| match super::ack_callback_msg::<Empty>(
+            deps.as_ref(),
+            status,
+            packet.clone(),
+            "nftcontract".to_string(),
+            None,
+        )
+        .unwrap()
+        .msg
+        {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => contract_addr,
+            _ => panic!("unexpected msg"),
+        };
+
+        // timeout callback takes precedence on timeout
+        assert_eq!(callback_addr(Ics721Status::Timeout), "timeoutaddr");
+        // ack callback for all others
+        assert_eq!(
+            callback_addr(Ics721Status::Failed("error".to_string())),
+            "ackaddr"
+        );
+        assert_eq!(callback_addr(Ics721Status::Success), "ackaddr");
+    }
+
+    #[test]
+    fn test_adr8_callbacks() {
+        let deps = mock_dependencies();
+        let memo = r#"{"src_callback":{"address":"srcaddr","gas_limit":"100000"},"dest_callback":{"address":"destaddr","msg":"ImRlc3Qi"},"forward":{}}"#;
+        let callbacks = super::parse_callback(Some(memo.to_string())).unwrap();
+        assert_eq!(
+            callbacks,
+            Ics721Callbacks {
+                ack_callback_data: Some(Binary::default()),
+                ack_callback_addr: Some("srcaddr".to_string()),
+                receive_callback_data: Some(to_json_binary("dest").unwrap()),
+                receive_callback_addr: Some("destaddr".to_string()),
+                timeout_callback_data: None,
+                timeout_callback_addr: None,
+            }
+        );
+
+        let packet = NonFungibleTokenPacketData {
+            class_id: ClassId::new("id"),
+            class_uri: None,
+            class_data: None,
+            token_ids: vec![TokenId::new("1")],
+            token_uris: None,
+            token_data: None,
+            sender: "sender".to_string(),
+            receiver: "receiver".to_string(),
+            memo: Some(memo.to_string()),
+        };
+        let sub_msg = super::ack_callback_msg::<Empty>(
+            deps.as_ref(),
+            Ics721Status::Success,
+            packet,
+            "nftcontract".to_string(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(sub_msg.gas_limit, Some(100000));
+        assert_eq!(sub_msg.reply_on, cosmwasm_std::ReplyOn::Error);
+        match sub_msg.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
+                assert_eq!(contract_addr, "srcaddr")
+            }
+            _ => panic!("unexpected msg"),
+        }
+    }
+
+    #[test]
+    fn test_voucher_cw721_code_id() {
+        use crate::state::{CHANNEL_CW721_CODE_IDS, CLASS_PREFIX_CW721_CODE_IDS, CW721_CODE_ID};
+
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        CW721_CODE_ID.save(storage, &1).unwrap();
+        let class_id = "wasm.ics721/channel-3/juno1collection";
+        assert_eq!(super::voucher_cw721_code_id(storage, class_id).unwrap(), 1);
+
+        CHANNEL_CW721_CODE_IDS
+            .save(storage, "channel-3".to_string(), &2)
+            .unwrap();
+        assert_eq!(super::voucher_cw721_code_id(storage, class_id).unwrap(), 2);
+        // other channel
+        assert_eq!(
+            super::voucher_cw721_code_id(storage, "wasm.ics721/channel-4/juno1collection").unwrap(),
+            1
+        );
+
+        // class ID prefix takes precedence over channel, the longest prefix wins
+        CLASS_PREFIX_CW721_CODE_IDS
+            .save(storage, "wasm.ics721/".to_string(), &3)
+            .unwrap();
+        CLASS_PREFIX_CW721_CODE_IDS
+            .save(storage, "wasm.ics721/channel-3/".to_string(), &4)
+            .unwrap();
+        CLASS_PREFIX_CW721_CODE_IDS
+            .save(storage, "wasm.other/".to_string(), &5)
+            .unwrap();
+        assert_eq!(super::voucher_cw721_code_id(storage, class_id).unwrap(), 4);
+        assert_eq!(
+            super::voucher_cw721_code_id(storage, "wasm.ics721/channel-4/stars1collection")
+                .unwrap(),
+            3
+        );
+
+        // full class ID matches
+        CLASS_PREFIX_CW721_CODE_IDS
+            .save(storage, class_id.to_string(), &6)
+            .unwrap();
+        assert_eq!(super::voucher_cw721_code_id(storage, class_id).unwrap(), 6);
+
+        // prefixes only match at `/` boundaries
+        CLASS_PREFIX_CW721_CODE_IDS
+            .save(storage, "wasm.ics721/channel-3/stars1".to_string(), &7)
+            .unwrap();
+        assert_eq!(
+            super::voucher_cw721_code_id(storage, "wasm.ics721/channel-3/stars1collection")
+                .unwrap(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_load_expired_claims() {
+        use cosmwasm_std::{Addr, Timestamp};
+
+        use crate::{
+            msg::ClaimPosition,
+            state::{Claim, CLAIMS},
+        };
+
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        let class_id = ClassId::new("id");
+        let claim = |expiration: Timestamp| Claim {
+            nft_contract: Addr::unchecked("nftcontract"),
+            sender: "sender".to_string(),
+            channel_id: "channel-0".to_string(),
+            expiration,
+        };
+        let key = |receiver: &str, token_id: &str| {
+            (
+                receiver.to_string(),
+                (class_id.clone(), TokenId::new(token_id)),
+            )
+        };
+        CLAIMS
+            .save(storage, key("d", "1"), &claim(Timestamp::from_seconds(300)))
+            .unwrap();
+        CLAIMS
+            .save(storage, key("c", "2"), &claim(Timestamp::from_seconds(200)))
+            .unwrap();
+        CLAIMS
+            .save(storage, key("b", "3"), &claim(Timestamp::from_seconds(100)))
+            .unwrap();
+        // refund claims never expire
+        CLAIMS
+            .save(
+                storage,
+                key("a", "4"),
+                &claim(Timestamp::from_nanos(u64::MAX)),
+            )
+            .unwrap();
+
+        // ordered by expiration, including claims expiring at given time
+        let now = Timestamp::from_seconds(200);
+        let expired = super::load_expired_claims(storage, now, None, None).unwrap();
+        assert_eq!(
+            expired,
+            vec![
+                (key("b", "3"), claim(Timestamp::from_seconds(100))),
+                (key("c", "2"), claim(Timestamp::from_seconds(200))),
+            ]
+        );
+
+        // paging
+        let expired = super::load_expired_claims(storage, now, None, Some(1)).unwrap();
+        assert_eq!(
+            expired,
+            vec![(key("b", "3"), claim(Timestamp::from_seconds(100)))]
+        );
+        let start_after = ClaimPosition {
+            expiration: Timestamp::from_seconds(100),
+            receiver: "b".to_string(),
+            class_id: class_id.clone(),
+            token_id: TokenId::new("3"),
+        };
+        let expired = super::load_expired_claims(storage, now, Some(start_after), None).unwrap();
+        assert_eq!(
+            expired,
+            vec![(key("c", "2"), claim(Timestamp::from_seconds(200)))]
+        );
+
+        // index is updated on removal
+        CLAIMS.remove(storage, key("b", "3")).unwrap();
+        let expired = super::load_expired_claims(storage, now, None, None).unwrap();
+        assert_eq!(
+            expired,
+            vec![(key("c", "2"), claim(Timestamp::from_seconds(200)))]
+        );
+    }
 }
@@ -0,0 +1,93 @@
+use cosmwasm_std::{Addr, CosmosMsg, Deps, DepsMut, Empty, Env, IbcPacket};
+use ics721_types::{ibc_types::NonFungibleTokenPacketData, types::Ics721Status};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::ContractError;
+
+/// Hooks for custom contracts built on the ics721 package, called on sending, receiving,
+/// ack and timeout of NFT transfers. Returned messages are added to the response. On send and
+/// receive, returning an error vetoes the transfer. By default hooks do nothing.
+///
+/// Ack and timeout hooks can't veto, since NFTs would be locked otherwise: an error is only added
+/// as `hook_error` attribute, and failing messages are ignored.
+pub trait Ics721Hooks<T = Empty>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    /// Called on `ReceiveNft`. Messages are executed before the packet is sent.
+    fn before_send(
+        &self,
+        _deps: DepsMut,
+        _env: &Env,
+        _channel_id: &str,
+        _data: &NonFungibleTokenPacketData,
+    ) -> Result<Vec<CosmosMsg<T>>, ContractError> {
+        Ok(vec![])
+    }
+
+    /// Called once the packet is sent, with its sequence (none in case the chain doesn't provide it).
+    /// Messages are executed after the packet is sent.
+    fn after_send(
+        &self,
+        _deps: DepsMut,
+        _env: &Env,
+        _channel_id: &str,
+        _sequence: Option<u64>,
+        _data: &NonFungibleTokenPacketData,
+    ) -> Result<Vec<CosmosMsg<T>>, ContractError> {
+        Ok(vec![])
+    }
+
+    /// Called on packet receive, before vouchers are created or redeemed. An error is returned
+    /// as a fail ack. Messages are executed within the receive submessage, before vouchers are
+    /// created or redeemed, so they are rolled back along with a fail ack.
+    fn before_receive(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _packet: &IbcPacket,
+        _data: &NonFungibleTokenPacketData,
+    ) -> Result<Vec<CosmosMsg<T>>, ContractError> {
+        Ok(vec![])
+    }
+
+    /// Called on packet receive, after `before_receive`. An error is returned as a fail ack.
+    /// Messages are executed within the receive submessage, after vouchers are created or
+    /// redeemed, so they are rolled back along with a fail ack.
+    fn after_receive(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _packet: &IbcPacket,
+        _data: &NonFungibleTokenPacketData,
+        _nft_contract: &Addr,
+    ) -> Result<Vec<CosmosMsg<T>>, ContractError> {
+        Ok(vec![])
+    }
+
+    /// Called on ack with read-only access, status is either success or failed. On ack fail, it is called before
+    /// NFTs are refunded. Can't veto: an error is added as `hook_error` attribute and no messages are added.
+    /// Messages are dispatched as submessages, failures are ignored.
+    fn on_ack(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _packet: &IbcPacket,
+        _data: &NonFungibleTokenPacketData,
+        _status: &Ics721Status,
+    ) -> Result<Vec<CosmosMsg<T>>, ContractError> {
+        Ok(vec![])
+    }
+
+    /// Called on timeout with read-only access, before NFTs are refunded. Same as `on_ack`, it can't veto and
+    /// failing messages are ignored.
+    fn on_timeout(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _packet: &IbcPacket,
+        _data: &NonFungibleTokenPacketData,
+    ) -> Result<Vec<CosmosMsg<T>>, ContractError> {
+        Ok(vec![])
+    }
+}
@@ -1,21 +1,30 @@
 use cosmwasm_std::{
-    from_json, to_json_binary, DepsMut, Empty, Env, Event, IbcBasicResponse, IbcChannelCloseMsg,
-    IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcPacket, IbcPacketAckMsg,
-    IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, Never, Reply, Response,
-    StdResult, SubMsgResult, WasmMsg,
+    from_json, to_json_binary, Addr, CosmosMsg, DepsMut, Empty, Env, Event, IbcBasicResponse,
+    IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcPacket,
+    IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, Never, Reply,
+    Response, StdError, StdResult, Storage, SubMsg, SubMsgResult, Timestamp, WasmMsg,
 };
 use cw_utils::parse_reply_instantiate_data;
-use ics721_types::{ibc_types::NonFungibleTokenPacketData, types::Ics721Status};
+use ics721_types::{
+    ibc_types::{Ics721ControlPacket, NonFungibleTokenPacketData},
+    types::Ics721Status,
+};
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
-    helpers::ack_callback_msg,
-    ibc_helpers::{ack_fail, ack_success, try_get_ack_error, validate_order_and_version},
+    helpers::{ack_callback_msg, add_history, outgoing_history, update_stats},
+    hooks::Ics721Hooks,
+    ibc_helpers::{
+        ack_fail, ack_success, ack_success_with_result, try_get_ack_error, try_get_ack_result,
+        validate_order_and_version,
+    },
     ibc_packet_receive::receive_ibc_packet,
     query::{load_class_id_for_nft_contract, load_nft_contract_for_class_id},
     state::{
-        IBC_RECEIVE_TOKEN_METADATA, INCOMING_CLASS_TOKEN_TO_CHANNEL, INCOMING_PROXY,
-        OUTGOING_CLASS_TOKEN_TO_CHANNEL, OUTGOING_PROXY,
+        Claim, HistoryAction, HistoryOutcome, PendingRefund, CLAIMS, IBC_RECEIVE_TOKEN_METADATA,
+        INBOX_FALLBACK, INCOMING_ACK_RESULT, INCOMING_CLASS_TOKEN_TO_CHANNEL, INCOMING_HISTORY,
+        INCOMING_PROXY, OUTGOING_CLASS_TOKEN_TO_CHANNEL, OUTGOING_PACKETS, OUTGOING_PROXY,
+        OUTGOING_REFUND_ADDRESS, PENDING_OUTGOING_PACKETS, PENDING_REFUNDS, RETURNED_CLAIMS,
     },
     ContractError,
 };
@@ -32,10 +41,16 @@ pub(crate) const INSTANTIATE_OUTGOING_PROXY_REPLY_ID: u64 = 2;
 pub(crate) const ACK_AND_DO_NOTHING_REPLY_ID: u64 = 3;
 /// Reply on callback
 pub(crate) const ACK_CALLBACK_REPLY_ID: u64 = 4;
+/// Reply on refunding NFTs, in case of ack fail or timeout.
+pub(crate) const REFUND_REPLY_ID: u64 = 5;
+/// Reply on sending a packet, for recording the in-flight packet by its sequence.
+pub(crate) const SEND_PACKET_REPLY_ID: u64 = 6;
+/// Reply on hook messages of ack and timeout, failures are ignored.
+pub(crate) const ACK_HOOK_REPLY_ID: u64 = 7;
 /// The IBC version this contract expects to communicate with.
 pub const IBC_VERSION: &str = "ics721-1";
 
-pub trait Ics721Ibc<T = Empty>
+pub trait Ics721Ibc<T = Empty>: Ics721Hooks<T>
 where
     T: Serialize + DeserializeOwned + Clone,
 {
@@ -98,11 +113,11 @@ where
         deps: DepsMut,
         env: Env,
         msg: IbcPacketReceiveMsg,
-    ) -> Result<IbcReceiveResponse, Never> {
+    ) -> Result<IbcReceiveResponse<T>, Never> {
         // Regardless of if our processing of this packet works we need to
         // commit an ACK to the chain. As such, we wrap all handling logic
         // in a seprate function and on error write out an error ack.
-        match receive_ibc_packet(deps, env, msg.packet) {
+        match receive_ibc_packet::<T, _>(self, deps, env, msg.packet) {
             Ok(response) => Ok(response),
             Err(error) => Ok(IbcReceiveResponse::new()
                 .add_attribute("method", "ibc_packet_receive")
@@ -113,14 +128,50 @@ where
 
     fn ibc_packet_ack(
         &self,
-        deps: DepsMut,
-        _env: Env,
+        mut deps: DepsMut,
+        env: Env,
         ack: IbcPacketAckMsg,
-    ) -> Result<IbcBasicResponse, ContractError> {
+    ) -> Result<IbcBasicResponse<T>, ContractError> {
+        // control packets are detected by their data, since control packets may have been disabled while in flight
+        if let Ok(control) = from_json::<Ics721ControlPacket>(&ack.original_packet.data) {
+            let status = match try_get_ack_error(&ack.acknowledgement) {
+                Some(error) => Ics721Status::Failed(error),
+                None => Ics721Status::Success,
+            };
+            return Ok(control_packet_response(
+                control,
+                &ack.original_packet,
+                status,
+            ));
+        }
+        settle_outgoing_packet(deps.storage, &ack.original_packet)?;
+        let msg: NonFungibleTokenPacketData = from_json(&ack.original_packet.data)?;
         if let Some(error) = try_get_ack_error(&ack.acknowledgement) {
-            self.handle_packet_fail(deps, ack.original_packet, &error)
+            let status = Ics721Status::Failed(error);
+            let hook_result = self.on_ack(deps.as_ref(), &env, &ack.original_packet, &msg, &status);
+            let response = self.handle_packet_fail(deps, env, ack.original_packet, status)?;
+            Ok(add_ack_hook_messages(response, hook_result))
         } else {
-            let msg: NonFungibleTokenPacketData = from_json(&ack.original_packet.data)?;
+            let hook_result = self.on_ack(
+                deps.as_ref(),
+                &env,
+                &ack.original_packet,
+                &msg,
+                &Ics721Status::Success,
+            );
+            add_history(
+                deps.storage,
+                outgoing_history(
+                    HistoryAction::Send,
+                    &msg,
+                    &ack.original_packet.src.channel_id,
+                    Some(ack.original_packet.sequence),
+                    env.block.height,
+                    HistoryOutcome::Success,
+                ),
+            )?;
+            // optional, since counterparty may ack with legacy "1" result
+            let ack_result = try_get_ack_result(&ack.acknowledgement);
 
             let nft_contract =
                 load_nft_contract_for_class_id(deps.storage, msg.class_id.to_string())?;
@@ -130,6 +181,9 @@ where
                 Vec::<WasmMsg>::new(),
                 |mut messages, token| -> StdResult<_> {
                     let key = (msg.class_id.clone(), token.clone());
+                    // transfer succeeded, so no refund needed
+                    OUTGOING_REFUND_ADDRESS.remove(deps.storage, key.clone());
+                    RETURNED_CLAIMS.remove(deps.storage, key.clone());
                     let source_channel =
                         INCOMING_CLASS_TOKEN_TO_CHANNEL.may_load(deps.storage, key.clone())?;
                     let returning_to_source = source_channel.map_or(false, |source_channel| {
@@ -137,7 +191,13 @@ where
                     });
                     if returning_to_source {
                         // This token's journey is complete, for now.
-                        INCOMING_CLASS_TOKEN_TO_CHANNEL.remove(deps.storage, key);
+                        update_stats(
+                            deps.storage,
+                            &ack.original_packet.src.channel_id,
+                            &msg.class_id,
+                            |stats| stats.vouchered = stats.vouchered.saturating_sub(1),
+                        )?;
+                        INCOMING_CLASS_TOKEN_TO_CHANNEL.remove(deps.storage, key)?;
                         IBC_RECEIVE_TOKEN_METADATA
                             .remove(deps.storage, (msg.class_id.clone(), token.clone()));
 
@@ -158,18 +218,24 @@ where
                 Ics721Status::Success,
                 msg.clone(),
                 nft_contract.to_string(),
+                ack_result.clone(),
             ) {
                 Some(msg) => vec![msg],
                 None => vec![],
             };
 
             let token_ids = format!("{:?}", msg.token_ids);
-            let event = Event::new("ics721_ack_burn_vouchers")
+            let mut event = Event::new("ics721_ack_burn_vouchers")
                 .add_attribute("nft_contract", nft_contract.clone())
                 .add_attribute("class_id", msg.class_id.to_string())
                 .add_attribute("token_ids", token_ids.clone());
+            if let Some(ack_result) = ack_result {
+                event = event
+                    .add_attribute("receiver_nft_contract", ack_result.nft_contract)
+                    .add_attribute("receive_action", ack_result.action.to_string());
+            }
 
-            Ok(IbcBasicResponse::new()
+            let response = IbcBasicResponse::new()
                 .add_attribute("burn_notices", (!burn_notices.is_empty()).to_string())
                 .add_attribute("method", "ibc_packet_ack_success")
                 .add_attribute("sender", msg.sender)
@@ -179,71 +245,99 @@ where
                 .add_attribute("token_ids", token_ids)
                 .add_messages(burn_notices)
                 .add_submessages(callback)
-                .add_event(event))
+                .add_event(event);
+            Ok(add_ack_hook_messages(response, hook_result))
         }
     }
 
     fn ibc_packet_timeout(
         &self,
         deps: DepsMut,
-        _env: Env,
+        env: Env,
         msg: IbcPacketTimeoutMsg,
-    ) -> Result<IbcBasicResponse, ContractError> {
-        self.handle_packet_fail(deps, msg.packet, "timeout")
+    ) -> Result<IbcBasicResponse<T>, ContractError> {
+        if let Ok(control) = from_json::<Ics721ControlPacket>(&msg.packet.data) {
+            return Ok(control_packet_response(
+                control,
+                &msg.packet,
+                Ics721Status::Timeout,
+            ));
+        }
+        settle_outgoing_packet(deps.storage, &msg.packet)?;
+        let data: NonFungibleTokenPacketData = from_json(&msg.packet.data)?;
+        let hook_result = self.on_timeout(deps.as_ref(), &env, &msg.packet, &data);
+        let response = self.handle_packet_fail(deps, env, msg.packet, Ics721Status::Timeout)?;
+        Ok(add_ack_hook_messages(response, hook_result))
     }
 
     /// Return the NFT locked in the ICS721 contract to sender; roll back.
     fn handle_packet_fail(
         &self,
-        deps: DepsMut,
+        mut deps: DepsMut,
+        env: Env,
         packet: IbcPacket,
-        error: &str,
-    ) -> Result<IbcBasicResponse, ContractError> {
+        status: Ics721Status,
+    ) -> Result<IbcBasicResponse<T>, ContractError> {
+        let error = match &status {
+            Ics721Status::Failed(error) => error.clone(),
+            Ics721Status::Timeout => "timeout".to_string(),
+            Ics721Status::Success => return Err(ContractError::UnexpectedAckStatus {}),
+        };
         let message: NonFungibleTokenPacketData = from_json(&packet.data)?;
         let nft_contract =
             load_nft_contract_for_class_id(deps.storage, message.class_id.to_string())?;
-        let sender = deps.api.addr_validate(&message.sender)?;
-
-        let messages = message
-            .token_ids
-            .iter()
-            .cloned()
-            .map(|token_id| -> StdResult<_> {
-                OUTGOING_CLASS_TOKEN_TO_CHANNEL
-                    .remove(deps.storage, (message.class_id.clone(), token_id.clone()));
-                Ok(WasmMsg::Execute {
-                    contract_addr: nft_contract.to_string(),
-                    msg: to_json_binary(&cw721_metadata_onchain::msg::ExecuteMsg::TransferNft {
-                        recipient: sender.to_string(),
-                        token_id: token_id.into(),
-                    })?,
-                    funds: vec![],
-                })
-            })
-            .collect::<StdResult<Vec<_>>>()?;
+        let (refunds, refund_addresses) = refund_messages(
+            deps.branch(),
+            &message,
+            &nft_contract,
+            &packet.src.channel_id,
+        )?;
+        add_history(
+            deps.storage,
+            outgoing_history(
+                HistoryAction::Refund,
+                &message,
+                &packet.src.channel_id,
+                Some(packet.sequence),
+                env.block.height,
+                HistoryOutcome::Failed {
+                    error: error.clone(),
+                },
+            ),
+        )?;
 
         let callback = match ack_callback_msg(
             deps.as_ref(),
-            Ics721Status::Failed(error.to_string()),
+            status,
             message.clone(),
             nft_contract.to_string(),
+            None,
         ) {
             Some(msg) => vec![msg],
             None => vec![],
         };
 
         Ok(IbcBasicResponse::new()
-            .add_messages(messages)
+            .add_submessages(
+                refunds
+                    .into_iter()
+                    .map(|refund| SubMsg::reply_always(refund, REFUND_REPLY_ID)),
+            )
             .add_submessages(callback)
             .add_attribute("method", "ibc_packet_ack_fail")
             .add_attribute("token_ids", format!("{:?}", message.token_ids))
             .add_attribute("class_id", message.class_id)
             .add_attribute("channel_id", packet.src.channel_id)
-            .add_attribute("address_refunded", message.sender)
+            .add_attribute("address_refunded", refund_addresses.join(","))
             .add_attribute("error", error))
     }
 
-    fn reply(&self, deps: DepsMut, _env: Env, reply: Reply) -> Result<Response<T>, ContractError> {
+    fn reply(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        reply: Reply,
+    ) -> Result<Response<T>, ContractError> {
         match reply.id {
             INSTANTIATE_CW721_REPLY_ID => {
                 // Don't need to add an ack or check for an error here as this
@@ -288,9 +382,36 @@ where
             // These messages don't need to do any state changes in the
             // reply - just need to commit an ack.
             ACK_AND_DO_NOTHING_REPLY_ID => {
+                // result is only given in case of success
+                let ack_result = INCOMING_ACK_RESULT.may_load(deps.storage)?;
+                INCOMING_ACK_RESULT.remove(deps.storage);
+                let incoming_history = INCOMING_HISTORY.may_load(deps.storage)?;
+                INCOMING_HISTORY.remove(deps.storage);
+                // in inbox mode, NFTs are held in custody in case delivery to receiver failed
+                let inbox_fallback = INBOX_FALLBACK.may_load(deps.storage)?;
+                INBOX_FALLBACK.remove(deps.storage);
                 match reply.result {
                     // On success, set a successful ack. Nothing else to do.
-                    SubMsgResult::Ok(_) => Ok(Response::new().set_data(ack_success())),
+                    SubMsgResult::Ok(_) => {
+                        add_history(deps.storage, incoming_history.unwrap_or_default())?;
+                        Ok(Response::new().set_data(
+                            ack_result.map_or_else(ack_success, |r| ack_success_with_result(&r)),
+                        ))
+                    }
+                    SubMsgResult::Err(err) if inbox_fallback.is_some() => {
+                        if let Some(ack_result) = ack_result {
+                            INCOMING_ACK_RESULT.save(deps.storage, &ack_result)?;
+                        }
+                        if let Some(incoming_history) = incoming_history {
+                            INCOMING_HISTORY.save(deps.storage, &incoming_history)?;
+                        }
+                        Ok(Response::new()
+                            .add_submessage(SubMsg::reply_always(
+                                inbox_fallback.unwrap(),
+                                ACK_AND_DO_NOTHING_REPLY_ID,
+                            ))
+                            .add_attribute("inbox_fallback", err))
+                    }
                     // On error we need to use set_data to override the data field
                     // from our caller, the IBC packet recv, and acknowledge our
                     // failure.  As per:
@@ -298,11 +419,253 @@ where
                     SubMsgResult::Err(err) => Ok(Response::new().set_data(ack_fail(err))),
                 }
             }
+            REFUND_REPLY_ID => {
+                // replies are in the same order as refunds were dispatched
+                let mut pending_refunds = PENDING_REFUNDS.load(deps.storage)?;
+                if pending_refunds.is_empty() {
+                    return Err(ContractError::NoPendingRefund {});
+                }
+                let refund = pending_refunds.remove(0);
+                if pending_refunds.is_empty() {
+                    PENDING_REFUNDS.remove(deps.storage);
+                } else {
+                    PENDING_REFUNDS.save(deps.storage, &pending_refunds)?;
+                }
+                match reply.result {
+                    SubMsgResult::Ok(_) => Ok(Response::new()),
+                    SubMsgResult::Err(err) => {
+                        // NFT is still escrowed by ics721, so refund address can claim it
+                        CLAIMS.save(
+                            deps.storage,
+                            (
+                                refund.refund_address.to_string(),
+                                (refund.class_id.clone(), refund.token_id.clone()),
+                            ),
+                            &Claim {
+                                nft_contract: refund.nft_contract,
+                                sender: refund.refund_address.to_string(),
+                                channel_id: refund.channel_id,
+                                // refund claims never expire
+                                expiration: Timestamp::from_nanos(u64::MAX),
+                            },
+                        )?;
+                        Ok(Response::new()
+                            .add_attribute("method", "refund_failed")
+                            .add_attribute("refund_address", refund.refund_address)
+                            .add_attribute("class_id", refund.class_id)
+                            .add_attribute("token_id", refund.token_id)
+                            .add_attribute("error", err))
+                    }
+                }
+            }
+            SEND_PACKET_REPLY_ID => {
+                // replies are in the same order as packets were dispatched
+                let mut pending_packets = PENDING_OUTGOING_PACKETS.load(deps.storage)?;
+                if pending_packets.is_empty() {
+                    return Err(ContractError::NoPendingOutgoingPacket {});
+                }
+                let outgoing_packet = pending_packets.remove(0);
+                if pending_packets.is_empty() {
+                    PENDING_OUTGOING_PACKETS.remove(deps.storage);
+                } else {
+                    PENDING_OUTGOING_PACKETS.save(deps.storage, &pending_packets)?;
+                }
+                let sequence = match reply.result {
+                    SubMsgResult::Ok(res) => res
+                        .data
+                        .and_then(|data| parse_send_packet_sequence(data.as_slice())),
+                    SubMsgResult::Err(err) => return Err(StdError::generic_err(err).into()),
+                };
+                add_history(
+                    deps.storage,
+                    outgoing_history(
+                        HistoryAction::Send,
+                        &outgoing_packet.data,
+                        &outgoing_packet.channel_id,
+                        sequence,
+                        env.block.height,
+                        HistoryOutcome::Pending,
+                    ),
+                )?;
+                let after_send_messages = self.after_send(
+                    deps.branch(),
+                    &env,
+                    &outgoing_packet.channel_id,
+                    sequence,
+                    &outgoing_packet.data,
+                )?;
+                let response = Response::new()
+                    .add_messages(after_send_messages)
+                    .add_attribute("method", "send_packet_reply");
+                match sequence {
+                    Some(sequence) => {
+                        let channel_id = outgoing_packet.channel_id.clone();
+                        OUTGOING_PACKETS.save(
+                            deps.storage,
+                            (channel_id.clone(), sequence),
+                            &outgoing_packet,
+                        )?;
+                        Ok(response
+                            .add_attribute("channel_id", channel_id)
+                            .add_attribute("sequence", sequence.to_string()))
+                    }
+                    // chain doesn't provide sequence (wasmd < v0.40.0), so packet
+                    // isn't recorded and can't be recovered by `RecoverPacket`
+                    None => Ok(response
+                        .add_attribute("sequence", "none")
+                        .add_attribute("recoverable", "false")),
+                }
+            }
             ACK_CALLBACK_REPLY_ID => {
                 let err = reply.result.unwrap_err();
                 Ok(Response::new().add_attribute("error", err))
             }
+            ACK_HOOK_REPLY_ID => {
+                let err = reply.result.unwrap_err();
+                Ok(Response::new().add_attribute("hook_error", err))
+            }
             _ => Err(ContractError::UnrecognisedReplyId {}),
         }
     }
 }
+
+/// Control packets don't escrow any NFTs, so there is nothing to roll back on ack fail or timeout.
+/// Ack and timeout must never fail because of hooks, otherwise the packet can't be settled and NFTs are locked.
+/// An error returned by the hook is added as `hook_error` attribute, and hook messages are dispatched as
+/// submessages, ignoring failures in reply.
+fn add_ack_hook_messages<T>(
+    response: IbcBasicResponse<T>,
+    hook_result: Result<Vec<CosmosMsg<T>>, ContractError>,
+) -> IbcBasicResponse<T> {
+    match hook_result {
+        Ok(messages) => response.add_submessages(
+            messages
+                .into_iter()
+                .map(|message| SubMsg::reply_on_error(message, ACK_HOOK_REPLY_ID)),
+        ),
+        Err(err) => response.add_attribute("hook_error", err.to_string()),
+    }
+}
+
+fn control_packet_response<T>(
+    control: Ics721ControlPacket,
+    packet: &IbcPacket,
+    status: Ics721Status,
+) -> IbcBasicResponse<T> {
+    let response = match control {
+        Ics721ControlPacket::TransferCreatorship {
+            class_id,
+            sender,
+            new_creator,
+            ..
+        } => IbcBasicResponse::new()
+            .add_attribute("method", "ibc_packet_ack_transfer_creatorship")
+            .add_attribute("class_id", class_id)
+            .add_attribute("sender", sender)
+            .add_attribute(
+                "new_creator",
+                new_creator.unwrap_or_else(|| "sender".to_string()),
+            ),
+    }
+    .add_attribute("channel_id", packet.src.channel_id.clone());
+    match status {
+        Ics721Status::Success => response.add_attribute("status", "success"),
+        Ics721Status::Failed(error) => response
+            .add_attribute("status", "failed")
+            .add_attribute("error", error),
+        Ics721Status::Timeout => response
+            .add_attribute("status", "timeout")
+            .add_attribute("error", "timeout"),
+    }
+}
+
+/// Removes the outgoing packet, once acked or timed out. Voided packets are rejected, since NFTs have already been refunded.
+pub(crate) fn settle_outgoing_packet(
+    storage: &mut dyn Storage,
+    packet: &IbcPacket,
+) -> Result<(), ContractError> {
+    let key = (packet.src.channel_id.clone(), packet.sequence);
+    if let Some(outgoing_packet) = OUTGOING_PACKETS.may_load(storage, key.clone())? {
+        if outgoing_packet.voided {
+            return Err(ContractError::PacketVoided {
+                channel: packet.src.channel_id.clone(),
+                sequence: packet.sequence,
+            });
+        }
+        OUTGOING_PACKETS.remove(storage, key);
+    }
+    Ok(())
+}
+
+/// Messages returning escrowed NFTs to refund address (or sender). Refunds are saved as pending,
+/// so in case refund fails NFTs are held in custody as a claim. Returned claims are restored instead of refunded.
+/// Returns messages and refund addresses.
+pub(crate) fn refund_messages(
+    deps: DepsMut,
+    message: &NonFungibleTokenPacketData,
+    nft_contract: &Addr,
+    channel_id: &str,
+) -> Result<(Vec<WasmMsg>, Vec<String>), ContractError> {
+    let sender = deps.api.addr_validate(&message.sender)?;
+    let mut pending_refunds = PENDING_REFUNDS.may_load(deps.storage)?.unwrap_or_default();
+    let mut messages = Vec::with_capacity(message.token_ids.len());
+    let mut refund_addresses: Vec<String> = Vec::with_capacity(message.token_ids.len());
+    for token_id in message.token_ids.iter().cloned() {
+        let key = (message.class_id.clone(), token_id.clone());
+        // vouchers sent back aren't escrowed
+        let is_voucher = INCOMING_CLASS_TOKEN_TO_CHANNEL.has(deps.storage, key.clone());
+        update_stats(deps.storage, channel_id, &message.class_id, |stats| {
+            stats.refunded += 1;
+            if !is_voucher {
+                stats.escrowed = stats.escrowed.saturating_sub(1);
+            }
+        })?;
+        OUTGOING_CLASS_TOKEN_TO_CHANNEL.remove(deps.storage, key.clone())?;
+        // NFT of a returned claim stays in custody of ICS721, so its claim is restored
+        if let Some((receiver, claim)) = RETURNED_CLAIMS.may_load(deps.storage, key.clone())? {
+            RETURNED_CLAIMS.remove(deps.storage, key.clone());
+            CLAIMS.save(deps.storage, (receiver, key), &claim)?;
+            continue;
+        }
+        let refund_address = OUTGOING_REFUND_ADDRESS
+            .may_load(deps.storage, key.clone())?
+            .unwrap_or_else(|| sender.clone());
+        OUTGOING_REFUND_ADDRESS.remove(deps.storage, key);
+        messages.push(WasmMsg::Execute {
+            contract_addr: nft_contract.to_string(),
+            msg: to_json_binary(&cw721_metadata_onchain::msg::ExecuteMsg::TransferNft {
+                recipient: refund_address.to_string(),
+                token_id: token_id.clone().into(),
+            })?,
+            funds: vec![],
+        });
+        if !refund_addresses.contains(&refund_address.to_string()) {
+            refund_addresses.push(refund_address.to_string());
+        }
+        pending_refunds.push(PendingRefund {
+            class_id: message.class_id.clone(),
+            token_id,
+            nft_contract: nft_contract.clone(),
+            refund_address,
+            channel_id: channel_id.to_string(),
+        });
+    }
+    PENDING_REFUNDS.save(deps.storage, &pending_refunds)?;
+    Ok((messages, refund_addresses))
+}
+
+/// Parses the sequence from `MsgIbcSendResponse { sequence: u64 }` (protobuf field 1, varint).
+fn parse_send_packet_sequence(data: &[u8]) -> Option<u64> {
+    let (tag, varint) = data.split_first()?;
+    if *tag != 0x08 {
+        return None;
+    }
+    let mut sequence: u64 = 0;
+    for (i, byte) in varint.iter().enumerate().take(10) {
+        sequence |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some(sequence);
+        }
+    }
+    None
+}
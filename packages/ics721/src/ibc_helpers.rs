@@ -1,6 +1,7 @@
 use cosmwasm_std::{
     from_json, to_json_binary, Binary, IbcAcknowledgement, IbcChannel, IbcEndpoint, IbcOrder,
 };
+use ics721_types::types::Ics721AckResult;
 use serde::{Deserialize, Serialize};
 
 use crate::{ibc::IBC_VERSION, ContractError};
@@ -39,11 +40,18 @@ pub enum Ics721Ack {
     Error(String),
 }
 
+/// Legacy success ACK, only holding "1" as a result.
 pub fn ack_success() -> Binary {
     let res = Ics721Ack::Result(b"1".into());
     to_json_binary(&res).unwrap()
 }
 
+/// Success ACK holding the voucher contract and the action taken on the receiving chain.
+pub fn ack_success_with_result(result: &Ics721AckResult) -> Binary {
+    let res = Ics721Ack::Result(to_json_binary(result).unwrap());
+    to_json_binary(&res).unwrap()
+}
+
 pub fn ack_fail(err: String) -> Binary {
     let res = Ics721Ack::Error(err);
     to_json_binary(&res).unwrap()
@@ -80,6 +88,15 @@ pub fn try_get_ack_error(ack: &IbcAcknowledgement) -> Option<String> {
     }
 }
 
+/// Tries to get the result from a success ACK. Returns `None` for
+/// error ACKs and for counterparties acking with a legacy "1" result.
+pub fn try_get_ack_result(ack: &IbcAcknowledgement) -> Option<Ics721AckResult> {
+    match from_json::<Ics721Ack>(&ack.data).ok()? {
+        Ics721Ack::Result(data) => from_json(data).ok(),
+        Ics721Ack::Error(_) => None,
+    }
+}
+
 /// Validates order and version information for ics721. We expect
 /// ics721-1 as the version and an unordered channel.
 pub(crate) fn validate_order_and_version(
@@ -119,8 +136,30 @@ pub(crate) fn validate_order_and_version(
 
 #[cfg(test)]
 mod tests {
+    use ics721_types::types::Ics721ReceiveAction;
+
     use super::*;
 
+    #[test]
+    fn test_ack_result() {
+        let result = Ics721AckResult {
+            nft_contract: "nft".to_string(),
+            action: Ics721ReceiveAction::Mint,
+        };
+        let ack = IbcAcknowledgement::new(ack_success_with_result(&result));
+        assert_eq!(try_get_ack_error(&ack), None);
+        assert_eq!(try_get_ack_result(&ack), Some(result));
+
+        // legacy ack
+        let ack = IbcAcknowledgement::new(ack_success());
+        assert_eq!(try_get_ack_error(&ack), None);
+        assert_eq!(try_get_ack_result(&ack), None);
+
+        let ack = IbcAcknowledgement::new(ack_fail("error".to_string()));
+        assert_eq!(try_get_ack_error(&ack), Some("error".to_string()));
+        assert_eq!(try_get_ack_result(&ack), None);
+    }
+
     #[test]
     fn test_pop_source_simple() {
         assert_eq!(
@@ -1,35 +1,60 @@
 use cosmwasm_std::{
-    from_json, to_json_binary, Addr, Binary, Deps, DepsMut, Empty, Env, IbcPacket,
+    from_json, to_json_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, IbcPacket,
     IbcReceiveResponse, StdResult, SubMsg, WasmMsg,
 };
+use serde::{de::DeserializeOwned, Serialize};
 use zip_optional::Zippable;
 
 use crate::{
-    helpers::{generate_receive_callback_msg, get_incoming_proxy_msg, get_receive_callback},
+    helpers::{
+        control_packets_enabled, generate_receive_callback_msg, get_incoming_proxy_msg,
+        get_receive_callback, get_send_nft_hook, get_wasm_hook, is_voucher_creator,
+        resolve_receiver, send_nft_msgs,
+    },
+    hooks::Ics721Hooks,
     ibc::ACK_AND_DO_NOTHING_REPLY_ID,
-    ibc_helpers::{get_endpoint_prefix, try_pop_source_prefix},
+    ibc_helpers::{ack_success, get_endpoint_prefix, try_pop_source_prefix},
     msg::{CallbackMsg, ExecuteMsg},
     query::{
         load_nft_contract_for_class_id, query_get_instantiate2_nft_contract,
         query_nft_contract_for_class_id,
     },
-    state::{CW721_CODE_ID, OUTGOING_CLASS_TOKEN_TO_CHANNEL, PO},
+    state::{
+        Claim, CollectionData, HistoryAction, HistoryOutcome, HistoryRecord, InboxConfig,
+        CLASS_ID_TO_CLASS, HISTORY_CONFIG, INBOX_CONFIG, INBOX_FALLBACK, INCOMING_ACK_RESULT,
+        INCOMING_HISTORY, OUTGOING_CLASS_TOKEN_TO_CHANNEL, PO,
+    },
     token_types::{VoucherCreation, VoucherRedemption},
+    utils::convert_owner_chain_address,
     ContractError,
 };
 use ics721_types::{
-    ibc_types::NonFungibleTokenPacketData,
+    ibc_types::{Ics721ControlPacket, NonFungibleTokenPacketData},
     token_types::{Class, ClassId, Token, TokenId},
+    types::{Ics721AckResult, Ics721ReceiveAction},
 };
 
-pub(crate) fn receive_ibc_packet(
+pub(crate) fn receive_ibc_packet<T, H>(
+    hooks: &H,
     deps: DepsMut,
     env: Env,
     packet: IbcPacket,
-) -> Result<IbcReceiveResponse, ContractError> {
+) -> Result<IbcReceiveResponse<T>, ContractError>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    H: Ics721Hooks<T> + ?Sized,
+{
     PO.error_if_paused(deps.storage)?;
+    // control packets are only understood by cw-ics721 counterparties, so parsed on opted-in channels only
+    if control_packets_enabled(deps.storage, &packet.dest.channel_id)? {
+        if let Ok(control) = from_json::<Ics721ControlPacket>(&packet.data) {
+            return receive_control_packet(deps, env, packet, control);
+        }
+    }
     let data: NonFungibleTokenPacketData = from_json(&packet.data)?;
     data.validate()?;
+    // hooks are read-only, their messages are executed within the receive submessage
+    let before_receive_messages = hooks.before_receive(deps.as_ref(), &env, &packet, &data)?;
 
     // Check if NFT is local if not get the local class id
     let maybe_local_class_id = try_pop_source_prefix(&packet.src, &data.class_id);
@@ -41,7 +66,33 @@ pub(crate) fn receive_ibc_packet(
         ClassId::new(format!("{}{}", local_prefix, data.class_id))
     };
 
-    // sub message holds 2 to 4 messages:
+    // optional wasm hook or delivery to receiver via `SendNft`:
+    // NFTs are escrowed by ics721 first and then sent to the hook contract
+    let wasm_hook = match get_wasm_hook(&data) {
+        Some(wasm_hook) => Some(wasm_hook),
+        None => get_send_nft_hook(deps.as_ref(), &data)?,
+    };
+    // inbox mode: NFTs not deliverable to receiver are held in custody by ics721, claimable by receiver
+    let inbox_config = INBOX_CONFIG.may_load(deps.storage)?;
+    let to_inbox = inbox_config.is_some()
+        && wasm_hook.is_none()
+        && resolve_receiver(deps.as_ref(), &env, &packet.dest.channel_id, &data.receiver).is_err();
+    let voucher_receiver = match &wasm_hook {
+        Some(wasm_hook) => {
+            if wasm_hook.contract != data.receiver {
+                return Err(ContractError::WasmHookReceiverMismatch {
+                    receiver: data.receiver,
+                    contract: wasm_hook.contract.clone(),
+                });
+            }
+            deps.api.addr_validate(&wasm_hook.contract)?;
+            Some(env.contract.address.clone())
+        }
+        None if to_inbox => Some(env.contract.address.clone()),
+        None => None,
+    };
+
+    // sub message holds 2 to 4 messages (plus send nft messages in case of a wasm hook):
     // - one message for voucher creation or redemption, another message for updating incoming or outgoing channel
     let (is_redemption, voucher_and_channel_messages) = create_voucher_and_channel_messages(
         deps.as_ref(),
@@ -50,6 +101,7 @@ pub(crate) fn receive_ibc_packet(
         maybe_local_class_id,
         local_class_id.clone(),
         packet.clone(),
+        voucher_receiver,
     )?;
     // - one optional incoming proxy message
     let incoming_proxy_msg =
@@ -67,13 +119,13 @@ pub(crate) fn receive_ibc_packet(
         match nft_contract {
             Some(nft_contract) => Ok(nft_contract),
             None => {
-                // contract not yet instantiated, so we use instantiate2 to get the contract address
-                let cw721_code_id = CW721_CODE_ID.load(deps.storage)?;
+                // contract not yet instantiated, so we use instantiate2 to get the contract address,
+                // using the same code ID (incl. overrides) as for instantiating the contract
                 query_get_instantiate2_nft_contract(
                     deps.as_ref(),
                     &env,
                     local_class_id.clone(),
-                    Some(cw721_code_id),
+                    None,
                 )
             }
         }
@@ -81,20 +133,132 @@ pub(crate) fn receive_ibc_packet(
 
     let callback_msg =
         create_callback_msg(deps.as_ref(), &data, nft_contract.to_string(), callback)?;
+    let after_receive_messages =
+        hooks.after_receive(deps.as_ref(), &env, &packet, &data, &nft_contract)?;
+
+    // - optional send nft messages, once NFTs are escrowed by ics721
+    let hook_messages = match wasm_hook.clone() {
+        Some(wasm_hook) => send_nft_msgs(
+            &nft_contract,
+            &data.token_ids,
+            wasm_hook.contract,
+            wasm_hook.msg,
+        )?,
+        None => vec![],
+    };
+
+    // - optional claims message in inbox mode
+    let claims_msg = inbox_config
+        .as_ref()
+        .map(|inbox_config| {
+            add_claims_msg(
+                &env,
+                &data,
+                &packet,
+                &local_class_id,
+                &nft_contract,
+                inbox_config,
+            )
+        })
+        .transpose()?;
+
+    // in inbox mode, in case delivery to receiver fails, NFTs are held in custody as a fallback,
+    // but a failing wasm or send nft hook rejects the transfer
+    INBOX_FALLBACK.remove(deps.storage);
+    if !to_inbox && wasm_hook.is_none() {
+        if let Some(claims_msg) = claims_msg.clone() {
+            let (_, (voucher_message, channel_message)) = create_voucher_and_channel_messages(
+                deps.as_ref(),
+                env.clone(),
+                data.clone(),
+                maybe_local_class_id,
+                local_class_id.clone(),
+                packet.clone(),
+                Some(env.contract.address.clone()),
+            )?;
+            let inbox_fallback = with_receive_hooks(
+                &env.contract.address,
+                conjunction_msg(
+                    env.contract.address.clone(),
+                    voucher_message,
+                    vec![],
+                    Some(claims_msg),
+                    channel_message,
+                    // receiver hasn't received NFTs, so no receive callback
+                    None,
+                    incoming_proxy_msg.clone(),
+                )?,
+                &before_receive_messages,
+                &after_receive_messages,
+            )?;
+            INBOX_FALLBACK.save(deps.storage, &inbox_fallback)?;
+        }
+    }
+
+    // history is recorded once submessage succeeded
+    INCOMING_HISTORY.remove(deps.storage);
+    if HISTORY_CONFIG.exists(deps.storage) {
+        let address =
+            resolve_receiver(deps.as_ref(), &env, &packet.dest.channel_id, &data.receiver)
+                .map_or_else(|_| data.receiver.clone(), |receiver| receiver.to_string());
+        let records: Vec<HistoryRecord> = data
+            .token_ids
+            .iter()
+            .map(|token_id| HistoryRecord {
+                action: if is_redemption {
+                    HistoryAction::Redeem
+                } else {
+                    HistoryAction::Receive
+                },
+                class_id: local_class_id.clone(),
+                token_id: token_id.clone(),
+                channel_id: Some(packet.dest.channel_id.clone()),
+                sequence: Some(packet.sequence),
+                address: address.clone(),
+                counterparty: Some(data.sender.clone()),
+                height: env.block.height,
+                outcome: HistoryOutcome::Success,
+            })
+            .collect();
+        INCOMING_HISTORY.save(deps.storage, &records)?;
+    }
 
     let submessage = into_submessage(
         env.contract.address,
         voucher_and_channel_messages.0,
+        hook_messages,
+        if to_inbox { claims_msg } else { None },
         voucher_and_channel_messages.1,
         callback_msg,
         incoming_proxy_msg,
+        before_receive_messages,
+        after_receive_messages,
+    )?;
+
+    // result is provided in the success ack, once submessage succeeded
+    INCOMING_ACK_RESULT.save(
+        deps.storage,
+        &Ics721AckResult {
+            nft_contract: nft_contract.to_string(),
+            action: if is_redemption {
+                Ics721ReceiveAction::Redemption
+            } else {
+                Ics721ReceiveAction::Mint
+            },
+        },
     )?;
 
-    let response = if let Some(memo) = data.memo {
+    let mut response = if let Some(memo) = data.memo {
         IbcReceiveResponse::default().add_attribute("ics721_memo", memo)
     } else {
         IbcReceiveResponse::default()
     };
+    if let Some(wasm_hook) = wasm_hook {
+        response = response.add_attribute("send_nft_contract", wasm_hook.contract);
+    }
+    if to_inbox {
+        response = response.add_attribute("inbox_receiver", data.receiver);
+    }
 
     Ok(response
         .add_submessage(submessage)
@@ -106,6 +270,85 @@ pub(crate) fn receive_ibc_packet(
         .add_attribute("counterparty_channel", packet.src.channel_id))
 }
 
+/// Handles control packets sent by the ICS721 contract on the home chain.
+fn receive_control_packet<T>(
+    deps: DepsMut,
+    env: Env,
+    packet: IbcPacket,
+    control: Ics721ControlPacket,
+) -> Result<IbcReceiveResponse<T>, ContractError> {
+    match control {
+        Ics721ControlPacket::TransferCreatorship {
+            class_id,
+            class_data,
+            sender,
+            new_creator,
+        } => {
+            // class originates from counterparty, so it is a voucher collection on this chain
+            let local_prefix = get_endpoint_prefix(&packet.dest);
+            let local_class_id = ClassId::new(format!("{}{}", local_prefix, class_id));
+            let nft_contract =
+                load_nft_contract_for_class_id(deps.storage, local_class_id.to_string()).map_err(
+                    |_| ContractError::NoNftContractForClassId(local_class_id.to_string()),
+                )?;
+            let owner = from_json::<CollectionData>(&class_data)
+                .ok()
+                .and_then(|collection_data| collection_data.owner)
+                .ok_or_else(|| ContractError::NoCollectionOwner(class_id.to_string()))?;
+            if owner != sender {
+                return Err(ContractError::NotCollectionOwner {
+                    sender,
+                    class_id: class_id.to_string(),
+                });
+            }
+            if !is_voucher_creator(deps.as_ref(), &env, &nft_contract) {
+                return Err(ContractError::NotCreator(nft_contract.to_string()));
+            }
+            let new_creator = match new_creator {
+                Some(new_creator) => deps.api.addr_validate(&new_creator)?,
+                None => deps
+                    .api
+                    .addr_validate(&convert_owner_chain_address(&env, &sender)?)?,
+            };
+            // keep class data up to date, since owner on home chain may have changed
+            CLASS_ID_TO_CLASS.update(deps.storage, local_class_id.clone(), |class| {
+                class
+                    .map(|class| Class {
+                        data: Some(class_data),
+                        ..class
+                    })
+                    .ok_or_else(|| {
+                        ContractError::NoNftContractForClassId(local_class_id.to_string())
+                    })
+            })?;
+            // new creator needs to accept creatorship
+            let transfer_msg = WasmMsg::Execute {
+                contract_addr: nft_contract.to_string(),
+                msg: to_json_binary(
+                    &cw721_metadata_onchain::msg::ExecuteMsg::UpdateCreatorOwnership(
+                        cw_ownable::Action::TransferOwnership {
+                            new_owner: new_creator.to_string(),
+                            expiry: None,
+                        },
+                    ),
+                )?,
+                funds: vec![],
+            };
+
+            Ok(IbcReceiveResponse::new()
+                .set_ack(ack_success())
+                .add_message(transfer_msg)
+                .add_attribute("method", "receive_transfer_creatorship")
+                .add_attribute("class_id", local_class_id.to_string())
+                .add_attribute("nft_contract", nft_contract)
+                .add_attribute("sender", sender)
+                .add_attribute("new_creator", new_creator)
+                .add_attribute("local_channel", packet.dest.channel_id)
+                .add_attribute("counterparty_channel", packet.src.channel_id))
+        }
+    }
+}
+
 fn create_voucher_and_channel_messages(
     deps: Deps,
     env: Env,
@@ -113,6 +356,7 @@ fn create_voucher_and_channel_messages(
     maybe_local_class_id: Option<&str>,
     local_class_id: ClassId,
     packet: IbcPacket,
+    voucher_receiver: Option<Addr>,
 ) -> Result<(bool, (WasmMsg, WasmMsg)), ContractError> {
     let token_count = data.token_ids.len();
     let redemption_or_create = data
@@ -169,7 +413,11 @@ fn create_voucher_and_channel_messages(
         return Err(ContractError::InvalidTransferNoAction);
     };
 
-    let receiver = deps.api.addr_validate(&data.receiver)?;
+    // vouchers are minted or redeemed to the receiver, unless they're escrowed by ics721 first
+    let receiver = match voucher_receiver {
+        Some(voucher_receiver) => voucher_receiver,
+        None => resolve_receiver(deps, &env, &packet.dest.channel_id, &data.receiver)?,
+    };
     let voucher_and_channel_messages = match is_redemption {
         true => {
             let redemption = VoucherRedemption {
@@ -235,6 +483,40 @@ fn create_voucher_and_channel_messages(
     Ok((is_redemption, voucher_and_channel_messages))
 }
 
+/// Message for saving claims, in case NFTs are held in custody for the receiver.
+fn add_claims_msg(
+    env: &Env,
+    data: &NonFungibleTokenPacketData,
+    packet: &IbcPacket,
+    local_class_id: &ClassId,
+    nft_contract: &Addr,
+    inbox_config: &InboxConfig,
+) -> StdResult<WasmMsg> {
+    let claims = data
+        .token_ids
+        .iter()
+        .map(|token_id| {
+            (
+                (local_class_id.clone(), token_id.clone()),
+                Claim {
+                    nft_contract: nft_contract.clone(),
+                    sender: data.sender.clone(),
+                    channel_id: packet.dest.channel_id.clone(),
+                    expiration: env.block.time.plus_seconds(inbox_config.claim_expiry),
+                },
+            )
+        })
+        .collect();
+    Ok(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_json_binary(&ExecuteMsg::Callback(CallbackMsg::AddClaims {
+            receiver: data.receiver.clone(),
+            claims,
+        }))?,
+        funds: vec![],
+    })
+}
+
 fn create_callback_msg(
     deps: Deps,
     data: &NonFungibleTokenPacketData,
@@ -254,20 +536,92 @@ fn create_callback_msg(
     }
 }
 
-pub fn into_submessage(
+#[allow(clippy::too_many_arguments)]
+pub fn into_submessage<T: Serialize>(
     contract: Addr,
     voucher_message: WasmMsg,
+    hook_messages: Vec<WasmMsg>,
+    claims_message: Option<WasmMsg>,
     channel_message: WasmMsg,
     callback_msg: Option<WasmMsg>,
     incoming_proxy_msg: Option<WasmMsg>,
-) -> StdResult<SubMsg<Empty>> {
-    let mut operands = Vec::with_capacity(4); // 4 is the max number of submessages we can have
+    before_receive_messages: Vec<CosmosMsg<T>>,
+    after_receive_messages: Vec<CosmosMsg<T>>,
+) -> StdResult<SubMsg<T>> {
+    let message = conjunction_msg(
+        contract.clone(),
+        voucher_message,
+        hook_messages,
+        claims_message,
+        channel_message,
+        callback_msg,
+        incoming_proxy_msg,
+    )?;
+    let message = with_receive_hooks(
+        &contract,
+        message,
+        &before_receive_messages,
+        &after_receive_messages,
+    )?;
+    Ok(SubMsg::reply_always(message, ACK_AND_DO_NOTHING_REPLY_ID))
+}
+
+/// Wraps receive hook messages around the conjunction, so they are executed, or rolled back, along with it.
+fn with_receive_hooks<T: Serialize>(
+    contract: &Addr,
+    conjunction: WasmMsg,
+    before_receive_messages: &[CosmosMsg<T>],
+    after_receive_messages: &[CosmosMsg<T>],
+) -> StdResult<WasmMsg> {
+    if before_receive_messages.is_empty() && after_receive_messages.is_empty() {
+        return Ok(conjunction);
+    }
+    let callback = |msg: CallbackMsg| -> StdResult<WasmMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract.to_string(),
+            msg: to_json_binary(&ExecuteMsg::Callback(msg))?,
+            funds: vec![],
+        })
+    };
+    let mut operands = Vec::with_capacity(3);
+    if !before_receive_messages.is_empty() {
+        operands.push(callback(CallbackMsg::HookMessages {
+            messages: to_json_binary(before_receive_messages)?,
+        })?);
+    }
+    operands.push(conjunction);
+    if !after_receive_messages.is_empty() {
+        operands.push(callback(CallbackMsg::HookMessages {
+            messages: to_json_binary(after_receive_messages)?,
+        })?);
+    }
+    callback(CallbackMsg::Conjunction { operands })
+}
+
+fn conjunction_msg(
+    contract: Addr,
+    voucher_message: WasmMsg,
+    hook_messages: Vec<WasmMsg>,
+    claims_message: Option<WasmMsg>,
+    channel_message: WasmMsg,
+    callback_msg: Option<WasmMsg>,
+    incoming_proxy_msg: Option<WasmMsg>,
+) -> StdResult<WasmMsg> {
+    // 5 is the max number of submessages we can have, besides hook messages
+    let mut operands = Vec::with_capacity(5 + hook_messages.len());
     if let Some(incoming_proxy_msg) = incoming_proxy_msg {
         operands.push(incoming_proxy_msg)
     }
 
     operands.push(voucher_message);
 
+    // hook messages require NFTs being escrowed by ics721
+    operands.extend(hook_messages);
+
+    if let Some(claims_message) = claims_message {
+        operands.push(claims_message)
+    }
+
     if let Some(callback_msg) = callback_msg {
         operands.push(callback_msg)
     }
@@ -280,5 +634,5 @@ pub fn into_submessage(
         msg: to_json_binary(&ExecuteMsg::Callback(CallbackMsg::Conjunction { operands }))?,
         funds: vec![],
     };
-    Ok(SubMsg::reply_always(message, ACK_AND_DO_NOTHING_REPLY_ID))
+    Ok(message)
 }
@@ -1,6 +1,7 @@
 pub mod error;
 pub mod execute;
 pub mod helpers;
+pub mod hooks;
 pub mod ibc;
 pub mod ibc_helpers;
 pub mod ibc_packet_receive;
@@ -1,9 +1,15 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, WasmMsg};
+use cosmwasm_std::{Addr, Binary, IbcTimeout, Timestamp, WasmMsg};
 use cw721::receiver::Cw721ReceiveMsg;
 use cw_cii::ContractInstantiateInfo;
 
-use crate::token_types::{VoucherCreation, VoucherRedemption};
+use crate::{
+    state::{
+        Claim, HistoryConfig, HistoryRecord, InboxConfig, OutgoingPacket, TransferStats,
+        UnknownMetadataPolicy, VoucherFlavour, VoucherProfile,
+    },
+    token_types::{VoucherCreation, VoucherRedemption},
+};
 use ics721_types::token_types::{Class, ClassId, ClassToken, Token, TokenId};
 
 #[cw_serde]
@@ -68,6 +74,244 @@ pub enum ExecuteMsg {
         class_id: String,
         collection: String,
     },
+
+    /// Batch version of `AdminCleanAndBurnNft`, all entries are cleaned up atomically.
+    AdminCleanAndBurnNfts {
+        entries: Vec<AdminCleanAndBurnEntry>,
+    },
+
+    /// Batch version of `AdminCleanAndUnescrowNft`, all entries are cleaned up atomically.
+    AdminCleanAndUnescrowNfts {
+        entries: Vec<AdminCleanAndUnescrowEntry>,
+    },
+
+    /// Admin msg for delivering NFTs via `SendNft` (and `Cw721ReceiveMsg`) to receivers
+    /// being a contract. If disabled, only receivers opting in by `send_nft` memo are delivered this way.
+    AdminSetSendNftToContracts { enabled: bool },
+
+    /// Admin msg for enabling inbox mode. In case of none, inbox mode is disabled.
+    /// In inbox mode, NFTs that can't be delivered to receiver (invalid address or failed delivery)
+    /// are held in custody by ICS721 and can be claimed by the receiver.
+    AdminSetInbox { config: Option<InboxConfig> },
+
+    /// Claims NFTs held in custody for the raw `receiver` and transfers them to `recipient`.
+    Claim {
+        receiver: String,
+        recipient: String,
+        proof: ClaimProof,
+        limit: Option<u32>,
+    },
+
+    /// Returns NFTs of expired claims (ordered by expiration) to their senders on source chain.
+    /// Anyone can call this.
+    ReturnExpiredClaims {
+        start_after: Option<ClaimPosition>,
+        limit: Option<u32>,
+    },
+
+    /// Admin msg for converting receivers with a foreign bech32 prefix (e.g. `juno1…`) to the local prefix
+    /// (e.g. `stars1…`) for packets received on the given local channel. Only enable it for channels
+    /// to chains with the same coin type, otherwise receiver may not own the converted address.
+    AdminSetReceiverConversion { channel_id: String, enabled: bool },
+
+    /// Admin msg for setting the name service contract, resolving receivers being a name (e.g. `alice.stars`)
+    /// using `AssociatedAddress { name }` query. In case of none, names are not resolved.
+    AdminSetNameService { name_service: Option<String> },
+
+    /// Admin msg for recovering an in-flight packet, which can't be acked nor timed out
+    /// (e.g. light client expired or frozen). All NFTs of the packet are refunded,
+    /// and the packet is voided, so any later ack or timeout is rejected.
+    RecoverPacket { channel: String, sequence: u64 },
+
+    /// Admin msg for enabling the transfer history. In case of none, no history is recorded.
+    AdminSetHistory { config: Option<HistoryConfig> },
+
+    /// Removes history records older than the configured retention. Anyone can call this.
+    PruneHistory { limit: Option<u32> },
+
+    /// Admin msg for declaring the cw721 version of a code ID, so instantiate and mint msgs of
+    /// debt-vouchers match. In case of none, `VoucherFlavour::V019` is used.
+    AdminSetVoucherFlavour {
+        code_id: u64,
+        flavour: Option<VoucherFlavour>,
+    },
+
+    /// Admin msg for overriding the cw721 code ID of debt-voucher collections, either for a local channel
+    /// or for a class ID prefix. In case of none, the override is removed.
+    AdminSetCw721CodeIdOverride {
+        target: Cw721CodeIdTarget,
+        code_id: Option<u64>,
+    },
+
+    /// Admin msg for migrating a page of debt-voucher contracts (ordered by class ID) to a new code ID.
+    /// Only contracts having ICS721 as admin are migrated, others are skipped.
+    MigrateVoucherContracts {
+        new_code_id: u64,
+        msg: Binary,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Admin msg for rebuilding the channel index of a page of outgoing (or incoming) channel entries,
+    /// ordered by class ID and token ID. Required once after migrating from a version storing them as plain maps.
+    MigrateChannelIndexes {
+        incoming: bool,
+        start_after: Option<ClassToken>,
+        limit: Option<u32>,
+    },
+
+    /// Sets the local royalty recipient of a debt-voucher collection, replacing the default cw721 admin,
+    /// ICS721 admin, or ICS721 creator. The collection owner on home chain (given in class data) proves ownership
+    /// either by bech32 equivalence with sender, or by a secp256k1 signature of the owner's key
+    /// over `sha256("royalty/{chain_id}/{ics721 address}/{class_id}/{payment_address}/{nonce}")`, with nonce
+    /// given by `QueryMsg::RoyaltyRecipientNonce`.
+    SetRoyaltyRecipient {
+        class_id: String,
+        payment_address: String,
+        proof: ClaimProof,
+    },
+
+    /// Sets the voucher profile (name, symbol, description, image, external link) of a class ID, applied on
+    /// instantiation of the debt-voucher collection. In case the collection already exists and ICS721 is its
+    /// creator, the profile is pushed to the collection. Callable by admin or collection owner on home chain
+    /// (given in class data). In case of none, the profile is removed.
+    SetVoucherProfile {
+        class_id: String,
+        profile: Option<VoucherProfile>,
+    },
+
+    /// Admin msg for using an existing cw721 contract as debt-voucher contract of a class ID,
+    /// instead of instantiating a new one. ICS721 must be minter of the contract.
+    RegisterVoucherContract { class_id: String, contract: String },
+
+    /// Admin msg for setting the policy for incoming class and token data in an unknown format.
+    /// In case of none, `UnknownMetadataPolicy::StoreRaw` is used.
+    AdminSetUnknownMetadataPolicy {
+        policy: Option<UnknownMetadataPolicy>,
+    },
+
+    /// Admin msg for enabling control packets (e.g. `TransferCreatorship`) on the given local channel.
+    /// Control packets aren't part of ICS721, so only enable it for channels to cw-ics721 counterparties.
+    AdminSetControlPackets { channel_id: String, enabled: bool },
+
+    /// Sends a control packet on the channel, transferring creatorship of the debt-voucher collection on
+    /// the counterparty chain to the new creator (by default the sender's address on the counterparty chain).
+    /// Only callable by the owner of a collection native to this chain, on channels with control packets
+    /// enabled. The new creator has to accept
+    /// creatorship on the voucher collection afterwards.
+    TransferCreatorship {
+        collection: String,
+        channel_id: String,
+        new_creator: Option<String>,
+        timeout: IbcTimeout,
+    },
+}
+
+#[cw_serde]
+pub enum Cw721CodeIdTarget {
+    /// Vouchers received on the local channel.
+    Channel { channel_id: String },
+    /// Vouchers whose local class ID (e.g. `wasm.stars1…/channel-3/juno1…`) starts with the prefix.
+    /// Prefixes match at `/` boundaries only, so the prefix either ends with `/` (e.g. `wasm.stars1…/channel-3/`)
+    /// or is a full class ID.
+    ClassIdPrefix { prefix: String },
+}
+
+#[cw_serde]
+pub struct Cw721CodeIdOverridesResponse {
+    pub channels: Vec<(String, u64)>,
+    pub class_id_prefixes: Vec<(String, u64)>,
+}
+
+#[cw_serde]
+pub struct AdminCleanAndBurnEntry {
+    pub owner: String,
+    pub token_id: String,
+    pub class_id: String,
+    pub collection: String,
+}
+
+#[cw_serde]
+pub struct AdminCleanAndUnescrowEntry {
+    pub recipient: String,
+    pub token_id: String,
+    pub class_id: String,
+    pub collection: String,
+}
+
+/// Outcome of an admin clean-up for a token.
+#[cw_serde]
+pub struct AdminCleanOutcome {
+    pub class_id: String,
+    pub token_id: String,
+    pub action: AdminCleanAction,
+}
+
+#[cw_serde]
+pub enum AdminCleanAction {
+    /// State is cleaned up and NFT is burned.
+    Burn { owner: String },
+    /// State is cleaned up and NFT is transferred to recipient.
+    Transfer { recipient: String },
+    /// NFT doesn't exist, only state is cleaned up.
+    CleanStateOnly {},
+    /// Clean-up fails, so batch would be reverted.
+    Failed { error: String },
+}
+
+/// Result of an invariant check over a page of channel entries.
+#[cw_serde]
+pub struct InvariantCheckResponse {
+    /// Number of entries checked.
+    pub checked: u32,
+    /// Last entry checked, to be used as `start_after` for the next page.
+    /// `None` in case all entries are checked.
+    pub last: Option<ClassToken>,
+    pub violations: Vec<InvariantViolation>,
+}
+
+#[cw_serde]
+pub struct InvariantViolation {
+    pub class_id: ClassId,
+    pub token_id: TokenId,
+    pub channel_id: String,
+    pub kind: InvariantViolationKind,
+}
+
+#[cw_serde]
+pub enum InvariantViolationKind {
+    /// Entry is orphaned, there is no NFT contract for its class ID.
+    Orphaned {},
+    /// NFT doesn't exist on NFT contract.
+    Missing { nft_contract: Addr },
+    /// Escrowed NFT isn't owned by ICS721.
+    WrongOwner { nft_contract: Addr, owner: String },
+}
+
+/// Query msg of the name service contract.
+#[cw_serde]
+pub enum NameServiceQueryMsg {
+    /// Returns the address associated to the name.
+    AssociatedAddress { name: String },
+}
+
+/// Proof that the sender is the owner of the raw receiver, or of the collection owner on home chain.
+#[cw_serde]
+pub enum ClaimProof {
+    /// Sender's address holds the same bech32 data as the receiver (e.g. `juno1abc…` and `stars1abc…`).
+    Bech32 {},
+    /// Secp256k1 signature of the receiver's key
+    /// over `sha256("{chain_id}/{ics721 address}/{receiver}/{recipient}")`.
+    Signature { pub_key: Binary, signature: Binary },
+}
+
+/// Position of a claim, used as `start_after` for paging `ReturnExpiredClaims`.
+#[cw_serde]
+pub struct ClaimPosition {
+    pub expiration: Timestamp,
+    pub receiver: String,
+    pub class_id: ClassId,
+    pub token_id: TokenId,
 }
 
 #[cw_serde]
@@ -89,6 +333,11 @@ pub enum CallbackMsg {
     RedeemOutgoingChannelEntries(Vec<(ClassId, TokenId)>),
     /// Save all entries in incoming channel.
     AddIncomingChannelEntries(Vec<((ClassId, TokenId), String)>),
+    /// Save claims for NFTs held in custody for the raw receiver.
+    AddClaims {
+        receiver: String,
+        claims: Vec<((ClassId, TokenId), Claim)>,
+    },
     /// Mints a NFT of collection class_id for receiver with the
     /// provided id and metadata. Only callable by this contract.
     Mint {
@@ -109,6 +358,9 @@ pub enum CallbackMsg {
     /// to respond with a single ACK when a message calls for the
     /// execution of both `CreateVouchers` and `RedeemVouchers`.
     Conjunction { operands: Vec<WasmMsg> },
+    /// Dispatches the messages returned by receive hooks. Messages are serialized as
+    /// `Vec<CosmosMsg<T>>`, since callbacks aren't generic over the custom message type.
+    HookMessages { messages: Binary },
 }
 
 #[cw_serde]
@@ -127,7 +379,8 @@ pub enum QueryMsg {
     NftContract { class_id: String },
 
     /// Returns predictable NFT contract using instantiate2. If no
-    /// cw721_code_id is provided, default cw721_code_id from storage is used.
+    /// cw721_code_id is provided, the code ID for the class ID is used,
+    /// applying overrides of `Cw721CodeIdOverrides`.
     #[returns(::cosmwasm_std::Addr)]
     GetInstantiate2NftContract {
         class_id: String,
@@ -169,6 +422,31 @@ pub enum QueryMsg {
     #[returns(u64)]
     Cw721CodeId {},
 
+    /// Gets the local royalty recipient of a class ID, if set.
+    #[returns(Option<Addr>)]
+    RoyaltyRecipient { class_id: String },
+
+    /// Gets the nonce of a class ID, to be included in the next signed royalty recipient proof.
+    #[returns(u64)]
+    RoyaltyRecipientNonce { class_id: String },
+
+    /// Gets the voucher profile of a class ID, if set.
+    #[returns(Option<VoucherProfile>)]
+    VoucherProfile { class_id: String },
+
+    /// Gets the cw721 code ID overrides for local channels and class ID prefixes.
+    #[returns(Cw721CodeIdOverridesResponse)]
+    Cw721CodeIdOverrides {},
+
+    /// Gets the voucher flavour of the given code ID. In case of none, the flavour of the
+    /// configured `Cw721CodeId` is returned.
+    #[returns(VoucherFlavour)]
+    VoucherFlavour { code_id: Option<u64> },
+
+    /// Gets the policy for incoming class and token data in an unknown format.
+    #[returns(UnknownMetadataPolicy)]
+    UnknownMetadataPolicy {},
+
     /// Gets the admin address for instantiating new cw721 contracts. In case of None, contract is immutable.
     #[returns(Option<Option<::cosmwasm_std::Addr>>)]
     Cw721Admin {},
@@ -177,6 +455,82 @@ pub enum QueryMsg {
     #[returns(Option<u32>)]
     ContractAddrLength {},
 
+    /// Gets whether NFTs are delivered via `SendNft` to receivers being a contract.
+    #[returns(bool)]
+    SendNftToContracts {},
+
+    /// Gets whether receivers with a foreign bech32 prefix are converted for the given local channel.
+    #[returns(bool)]
+    ReceiverConversion { channel_id: String },
+
+    /// Gets whether control packets are enabled for the given local channel.
+    #[returns(bool)]
+    ControlPackets { channel_id: String },
+
+    /// Dry-run of `AdminCleanAndBurnNfts` and `AdminCleanAndUnescrowNfts`, listing the outcome for each token.
+    #[returns(Vec<AdminCleanOutcome>)]
+    AdminCleanDryRun {
+        burn: Vec<AdminCleanAndBurnEntry>,
+        unescrow: Vec<AdminCleanAndUnescrowEntry>,
+    },
+
+    /// Gets the in-flight packet for the given local channel and sequence.
+    #[returns(Option<OutgoingPacket>)]
+    OutgoingPacket { channel: String, sequence: u64 },
+
+    /// Gets the name service contract, if set.
+    #[returns(Option<Addr>)]
+    NameService {},
+
+    /// Gets the history config, if transfer history is enabled.
+    #[returns(Option<HistoryConfig>)]
+    HistoryConfig {},
+
+    /// Gets a list of record ID and history record, either for a local address
+    /// or for an NFT (class ID and token ID). In case none is given, all records are listed.
+    #[returns(Vec<(u64, HistoryRecord)>)]
+    History {
+        address: Option<String>,
+        class_id: Option<String>,
+        token_id: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Gets the inbox config, if inbox mode is enabled.
+    #[returns(Option<InboxConfig>)]
+    InboxConfig {},
+
+    /// Gets a list of classID, tokenID, and claim for NFTs held in custody for the raw receiver.
+    #[returns(Vec<((ClassId, TokenId), Claim)>)]
+    Claims {
+        receiver: String,
+        start_after: Option<ClassToken>,
+        limit: Option<u32>,
+    },
+
+    /// Gets transfer stats of the given local channel or class ID. In case none is given,
+    /// stats of all channels are summed up.
+    #[returns(TransferStats)]
+    Stats {
+        channel_id: Option<String>,
+        class_id: Option<String>,
+    },
+
+    /// Checks a page of `OutgoingChannels` entries, each NFT must be escrowed by ICS721.
+    #[returns(InvariantCheckResponse)]
+    CheckEscrowInvariants {
+        start_after: Option<ClassToken>,
+        limit: Option<u32>,
+    },
+
+    /// Checks a page of `IncomingChannels` entries, each voucher must exist.
+    #[returns(InvariantCheckResponse)]
+    CheckVoucherInvariants {
+        start_after: Option<ClassToken>,
+        limit: Option<u32>,
+    },
+
     /// Gets a list of classID as key (from
     /// NonFungibleTokenPacketData) and cw721 contract as value
     /// (instantiated for that classID).
@@ -188,18 +542,22 @@ pub enum QueryMsg {
 
     /// Gets a list of classID, tokenID, and local channelID. Used
     /// to determine the local channel that NFTs have been sent
-    /// out on.
+    /// out on. Optionally filtered by local channel and classID.
     #[returns(Vec<((ClassId, TokenId), String)>)]
     OutgoingChannels {
+        channel_id: Option<String>,
+        class_id: Option<String>,
         start_after: Option<ClassToken>,
         limit: Option<u32>,
     },
 
     /// Gets a list of classID, tokenID, and local channel ID. Used
     /// to determine the local channel that NFTs have arrived at
-    /// this contract.
+    /// this contract. Optionally filtered by local channel and classID.
     #[returns(Vec<((ClassId, TokenId), String)>)]
     IncomingChannels {
+        channel_id: Option<String>,
+        class_id: Option<String>,
         start_after: Option<ClassToken>,
         limit: Option<u32>,
     },
@@ -1,15 +1,26 @@
 use cosmwasm_std::{to_json_binary, Addr, Binary, Deps, Env, Order, StdError, StdResult, Storage};
-use cw_storage_plus::{Bound, Map};
+use cw_storage_plus::{Bound, IndexedMap};
 use sha2::{Digest, Sha256};
 
 use crate::{
-    helpers::get_instantiate2_address,
-    msg::QueryMsg,
+    helpers::{
+        control_packets_enabled, get_instantiate2_address, plan_clean_and_burn,
+        plan_clean_and_unescrow, query_nft_owner, voucher_cw721_code_id,
+    },
+    msg::{
+        AdminCleanAction, AdminCleanAndBurnEntry, AdminCleanAndUnescrowEntry, AdminCleanOutcome,
+        Cw721CodeIdOverridesResponse, InvariantCheckResponse, InvariantViolation,
+        InvariantViolationKind, QueryMsg,
+    },
     state::{
-        UniversalAllNftInfoResponse, CLASS_ID_AND_NFT_CONTRACT_INFO, CLASS_ID_TO_CLASS,
-        CONTRACT_ADDR_LENGTH, CW721_ADMIN, CW721_CODE_ID, IBC_RECEIVE_TOKEN_METADATA,
-        INCOMING_CLASS_TOKEN_TO_CHANNEL, INCOMING_PROXY, OUTGOING_CLASS_TOKEN_TO_CHANNEL,
-        OUTGOING_PROXY, PO,
+        Claim, ClassTokenToChannelIndexes, HistoryRecord, TransferStats,
+        UniversalAllNftInfoResponse, CHANNEL_CW721_CODE_IDS, CHANNEL_STATS, CLAIMS,
+        CLASS_ID_AND_NFT_CONTRACT_INFO, CLASS_ID_TO_CLASS, CLASS_PREFIX_CW721_CODE_IDS,
+        CLASS_STATS, CONTRACT_ADDR_LENGTH, CONVERT_RECEIVER_PREFIX, CW721_ADMIN,
+        CW721_CODE_FLAVOURS, CW721_CODE_ID, HISTORY, HISTORY_CONFIG, IBC_RECEIVE_TOKEN_METADATA,
+        INBOX_CONFIG, INCOMING_CLASS_TOKEN_TO_CHANNEL, INCOMING_PROXY, NAME_SERVICE,
+        OUTGOING_CLASS_TOKEN_TO_CHANNEL, OUTGOING_PACKETS, OUTGOING_PROXY, PO, ROYALTY_RECIPIENTS,
+        ROYALTY_RECIPIENT_NONCES, SEND_NFT_TO_CONTRACTS, UNKNOWN_METADATA_POLICY, VOUCHER_PROFILES,
     },
     ContractError,
 };
@@ -47,19 +58,148 @@ pub trait Ics721Query {
             QueryMsg::OutgoingProxy {} => Ok(to_json_binary(&OUTGOING_PROXY.load(deps.storage)?)?),
             QueryMsg::IncomingProxy {} => Ok(to_json_binary(&INCOMING_PROXY.load(deps.storage)?)?),
             QueryMsg::Cw721CodeId {} => Ok(to_json_binary(&query_cw721_code_id(deps)?)?),
+            QueryMsg::RoyaltyRecipient { class_id } => Ok(to_json_binary(
+                &ROYALTY_RECIPIENTS.may_load(deps.storage, ClassId::new(class_id))?,
+            )?),
+            QueryMsg::RoyaltyRecipientNonce { class_id } => Ok(to_json_binary(
+                &ROYALTY_RECIPIENT_NONCES
+                    .may_load(deps.storage, ClassId::new(class_id))?
+                    .unwrap_or_default(),
+            )?),
+            QueryMsg::VoucherProfile { class_id } => Ok(to_json_binary(
+                &VOUCHER_PROFILES.may_load(deps.storage, ClassId::new(class_id))?,
+            )?),
+            QueryMsg::Cw721CodeIdOverrides {} => {
+                Ok(to_json_binary(&Cw721CodeIdOverridesResponse {
+                    channels: CHANNEL_CW721_CODE_IDS
+                        .range(deps.storage, None, None, Order::Ascending)
+                        .collect::<StdResult<_>>()?,
+                    class_id_prefixes: CLASS_PREFIX_CW721_CODE_IDS
+                        .range(deps.storage, None, None, Order::Ascending)
+                        .collect::<StdResult<_>>()?,
+                })?)
+            }
+            QueryMsg::VoucherFlavour { code_id } => {
+                let code_id = match code_id {
+                    Some(code_id) => code_id,
+                    None => query_cw721_code_id(deps)?,
+                };
+                Ok(to_json_binary(
+                    &CW721_CODE_FLAVOURS
+                        .may_load(deps.storage, code_id)?
+                        .unwrap_or_default(),
+                )?)
+            }
+            QueryMsg::UnknownMetadataPolicy {} => Ok(to_json_binary(
+                &UNKNOWN_METADATA_POLICY
+                    .may_load(deps.storage)?
+                    .unwrap_or_default(),
+            )?),
             QueryMsg::Cw721Admin {} => Ok(to_json_binary(&CW721_ADMIN.load(deps.storage)?)?),
             QueryMsg::ContractAddrLength {} => Ok(to_json_binary(
                 &CONTRACT_ADDR_LENGTH.may_load(deps.storage)?,
             )?),
-            QueryMsg::NftContracts { start_after, limit } => Ok(to_json_binary(
-                &query_nft_contracts(deps, start_after, limit)?,
+            QueryMsg::SendNftToContracts {} => Ok(to_json_binary(
+                &SEND_NFT_TO_CONTRACTS
+                    .may_load(deps.storage)?
+                    .unwrap_or(false),
+            )?),
+            QueryMsg::ReceiverConversion { channel_id } => Ok(to_json_binary(
+                &CONVERT_RECEIVER_PREFIX
+                    .may_load(deps.storage, channel_id)?
+                    .unwrap_or(false),
+            )?),
+            QueryMsg::ControlPackets { channel_id } => Ok(to_json_binary(
+                &control_packets_enabled(deps.storage, &channel_id)?,
             )?),
-            QueryMsg::OutgoingChannels { start_after, limit } => Ok(to_json_binary(
-                &query_channels(deps, &OUTGOING_CLASS_TOKEN_TO_CHANNEL, start_after, limit)?,
+            QueryMsg::AdminCleanDryRun { burn, unescrow } => Ok(to_json_binary(
+                &query_admin_clean_dry_run(deps, &env, burn, unescrow),
             )?),
-            QueryMsg::IncomingChannels { start_after, limit } => Ok(to_json_binary(
-                &query_channels(deps, &INCOMING_CLASS_TOKEN_TO_CHANNEL, start_after, limit)?,
+            QueryMsg::OutgoingPacket { channel, sequence } => Ok(to_json_binary(
+                &OUTGOING_PACKETS.may_load(deps.storage, (channel, sequence))?,
+            )?),
+            QueryMsg::NameService {} => Ok(to_json_binary(&NAME_SERVICE.may_load(deps.storage)?)?),
+            QueryMsg::HistoryConfig {} => {
+                Ok(to_json_binary(&HISTORY_CONFIG.may_load(deps.storage)?)?)
+            }
+            QueryMsg::History {
+                address,
+                class_id,
+                token_id,
+                start_after,
+                limit,
+            } => Ok(to_json_binary(&query_history(
+                deps,
+                address,
+                class_id,
+                token_id,
+                start_after,
+                limit,
+            )?)?),
+            QueryMsg::InboxConfig {} => Ok(to_json_binary(&INBOX_CONFIG.may_load(deps.storage)?)?),
+            QueryMsg::Claims {
+                receiver,
+                start_after,
+                limit,
+            } => Ok(to_json_binary(&query_claims(
+                deps,
+                receiver,
+                start_after,
+                limit,
+            )?)?),
+            QueryMsg::Stats {
+                channel_id,
+                class_id,
+            } => Ok(to_json_binary(&query_stats(deps, channel_id, class_id)?)?),
+            QueryMsg::CheckEscrowInvariants { start_after, limit } => {
+                Ok(to_json_binary(&query_invariants(
+                    deps,
+                    &env,
+                    &OUTGOING_CLASS_TOKEN_TO_CHANNEL,
+                    true,
+                    start_after,
+                    limit,
+                )?)?)
+            }
+            QueryMsg::CheckVoucherInvariants { start_after, limit } => {
+                Ok(to_json_binary(&query_invariants(
+                    deps,
+                    &env,
+                    &INCOMING_CLASS_TOKEN_TO_CHANNEL,
+                    false,
+                    start_after,
+                    limit,
+                )?)?)
+            }
+            QueryMsg::NftContracts { start_after, limit } => Ok(to_json_binary(
+                &query_nft_contracts(deps, start_after, limit)?,
             )?),
+            QueryMsg::OutgoingChannels {
+                channel_id,
+                class_id,
+                start_after,
+                limit,
+            } => Ok(to_json_binary(&query_channels(
+                deps,
+                &OUTGOING_CLASS_TOKEN_TO_CHANNEL,
+                channel_id,
+                class_id,
+                start_after,
+                limit,
+            )?)?),
+            QueryMsg::IncomingChannels {
+                channel_id,
+                class_id,
+                start_after,
+                limit,
+            } => Ok(to_json_binary(&query_channels(
+                deps,
+                &INCOMING_CLASS_TOKEN_TO_CHANNEL,
+                channel_id,
+                class_id,
+                start_after,
+                limit,
+            )?)?),
         }
     }
 }
@@ -101,7 +241,7 @@ pub fn query_get_instantiate2_nft_contract(
     let cw721_code_id = if let Some(cw721_code_id) = cw721_code_id {
         cw721_code_id
     } else {
-        CW721_CODE_ID.load(deps.storage)?
+        voucher_cw721_code_id(deps.storage, &class_id)?
     };
 
     let mut hasher = Sha256::new();
@@ -196,23 +336,250 @@ pub fn query_nft_contracts(
     }
 }
 
-fn query_channels(
+pub fn query_admin_clean_dry_run(
     deps: Deps,
-    class_token_to_channel: &Map<(ClassId, TokenId), String>,
+    env: &Env,
+    burn: Vec<AdminCleanAndBurnEntry>,
+    unescrow: Vec<AdminCleanAndUnescrowEntry>,
+) -> Vec<AdminCleanOutcome> {
+    let burn_outcomes = burn.into_iter().map(|entry| {
+        let action = match deps
+            .api
+            .addr_validate(&entry.owner)
+            .map_err(ContractError::from)
+            .and_then(|_| plan_clean_and_burn(deps, &entry))
+        {
+            Ok(Some(_)) => AdminCleanAction::Burn {
+                owner: entry.owner.clone(),
+            },
+            Ok(None) => AdminCleanAction::CleanStateOnly {},
+            Err(err) => AdminCleanAction::Failed {
+                error: err.to_string(),
+            },
+        };
+        AdminCleanOutcome {
+            class_id: entry.class_id,
+            token_id: entry.token_id,
+            action,
+        }
+    });
+    let unescrow_outcomes = unescrow.into_iter().map(|entry| {
+        let action = match deps
+            .api
+            .addr_validate(&entry.recipient)
+            .map_err(ContractError::from)
+            .and_then(|_| plan_clean_and_unescrow(deps, env, &entry))
+        {
+            Ok(Some(_)) => AdminCleanAction::Transfer {
+                recipient: entry.recipient.clone(),
+            },
+            Ok(None) => AdminCleanAction::CleanStateOnly {},
+            Err(err) => AdminCleanAction::Failed {
+                error: err.to_string(),
+            },
+        };
+        AdminCleanOutcome {
+            class_id: entry.class_id,
+            token_id: entry.token_id,
+            action,
+        }
+    });
+    burn_outcomes.chain(unescrow_outcomes).collect()
+}
+
+pub fn query_claims(
+    deps: Deps,
+    receiver: String,
     start_after: Option<ClassToken>,
     limit: Option<u32>,
-) -> StdResult<Vec<((String, String), String)>> {
-    let start_after = start_after.map(|class_token| {
-        (
+) -> StdResult<Vec<((ClassId, TokenId), Claim)>> {
+    let start = start_after.map(|class_token| {
+        Bound::exclusive((
             ClassId::new(class_token.class_id),
             TokenId::new(class_token.token_id),
-        )
+        ))
     });
-    cw_paginate_storage::paginate_map(
-        deps,
-        class_token_to_channel,
-        start_after,
-        limit,
-        Order::Ascending,
-    )
+    let all = CLAIMS
+        .prefix(receiver)
+        .range(deps.storage, start, None, Order::Ascending);
+    match limit {
+        Some(limit) => all.take(limit as usize).collect(),
+        None => all.collect(),
+    }
+}
+
+pub fn query_history(
+    deps: Deps,
+    address: Option<String>,
+    class_id: Option<String>,
+    token_id: Option<String>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, HistoryRecord)>> {
+    let start = start_after.map(Bound::exclusive);
+    let all: Box<dyn Iterator<Item = StdResult<(u64, HistoryRecord)>> + '_> =
+        match (address, class_id, token_id) {
+            (Some(address), None, None) => Box::new(HISTORY.idx.address.prefix(address).range(
+                deps.storage,
+                start,
+                None,
+                Order::Ascending,
+            )),
+            (None, Some(class_id), Some(token_id)) => {
+                Box::new(HISTORY.idx.class_token.prefix((class_id, token_id)).range(
+                    deps.storage,
+                    start,
+                    None,
+                    Order::Ascending,
+                ))
+            }
+            (None, None, None) => {
+                Box::new(HISTORY.range(deps.storage, start, None, Order::Ascending))
+            }
+            _ => {
+                return Err(StdError::generic_err(
+                    "history is either queried by address or by class_id and token_id",
+                ))
+            }
+        };
+    match limit {
+        Some(limit) => all.take(limit as usize).collect(),
+        None => all.collect(),
+    }
+}
+
+pub fn query_stats(
+    deps: Deps,
+    channel_id: Option<String>,
+    class_id: Option<String>,
+) -> StdResult<TransferStats> {
+    match (channel_id, class_id) {
+        (Some(channel_id), None) => Ok(CHANNEL_STATS
+            .may_load(deps.storage, channel_id)?
+            .unwrap_or_default()),
+        (None, Some(class_id)) => Ok(CLASS_STATS
+            .may_load(deps.storage, ClassId::new(class_id))?
+            .unwrap_or_default()),
+        (None, None) => CHANNEL_STATS
+            .range(deps.storage, None, None, Order::Ascending)
+            .try_fold(TransferStats::default(), |mut total, item| {
+                let (_, stats) = item?;
+                total.sent += stats.sent;
+                total.received += stats.received;
+                total.redeemed += stats.redeemed;
+                total.refunded += stats.refunded;
+                total.escrowed += stats.escrowed;
+                total.vouchered += stats.vouchered;
+                Ok(total)
+            }),
+        (Some(_), Some(_)) => Err(StdError::generic_err(
+            "stats are either queried by channel_id or class_id",
+        )),
+    }
+}
+
+/// Default page size for invariant checks, since each entry queries its NFT contract.
+const DEFAULT_INVARIANT_CHECK_LIMIT: u32 = 30;
+
+/// Checks a page of channel entries against their NFT contracts. In case `escrowed` is true,
+/// NFTs must be owned by ICS721, otherwise they must only exist.
+fn query_invariants(
+    deps: Deps,
+    env: &Env,
+    class_token_to_channel: &IndexedMap<(ClassId, TokenId), String, ClassTokenToChannelIndexes>,
+    escrowed: bool,
+    start_after: Option<ClassToken>,
+    limit: Option<u32>,
+) -> StdResult<InvariantCheckResponse> {
+    let limit = limit.unwrap_or(DEFAULT_INVARIANT_CHECK_LIMIT) as usize;
+    let start = start_after
+        .map(|class_token| Bound::exclusive((class_token.class_id, class_token.token_id)));
+    let entries = class_token_to_channel
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut violations = vec![];
+    for ((class_id, token_id), channel_id) in entries.iter().cloned() {
+        let kind = match query_nft_contract_for_class_id(deps.storage, ClassId::new(&class_id))? {
+            None => Some(InvariantViolationKind::Orphaned {}),
+            Some(nft_contract) => match query_nft_owner(deps, &nft_contract, &token_id) {
+                None => Some(InvariantViolationKind::Missing { nft_contract }),
+                Some(owner) if escrowed && owner != env.contract.address.as_str() => {
+                    Some(InvariantViolationKind::WrongOwner {
+                        nft_contract,
+                        owner,
+                    })
+                }
+                Some(_) => None,
+            },
+        };
+        if let Some(kind) = kind {
+            violations.push(InvariantViolation {
+                class_id: ClassId::new(class_id),
+                token_id: TokenId::new(token_id),
+                channel_id,
+                kind,
+            });
+        }
+    }
+
+    // in case page is full, there may be more entries to check
+    let last = if entries.len() == limit {
+        entries.last().map(|((class_id, token_id), _)| ClassToken {
+            class_id: ClassId::new(class_id),
+            token_id: TokenId::new(token_id),
+        })
+    } else {
+        None
+    };
+    Ok(InvariantCheckResponse {
+        checked: entries.len() as u32,
+        last,
+        violations,
+    })
+}
+
+fn query_channels(
+    deps: Deps,
+    class_token_to_channel: &IndexedMap<(ClassId, TokenId), String, ClassTokenToChannelIndexes>,
+    channel_id: Option<String>,
+    class_id: Option<String>,
+    start_after: Option<ClassToken>,
+    limit: Option<u32>,
+) -> StdResult<Vec<((String, String), String)>> {
+    let start = match (start_after, &class_id) {
+        (Some(class_token), None) => Some(Bound::exclusive((
+            class_token.class_id,
+            class_token.token_id,
+        ))),
+        // in case of class filter, only the token ID of start_after is relevant
+        (Some(class_token), Some(class_id)) => Some(Bound::exclusive((
+            ClassId::new(class_id),
+            class_token.token_id,
+        ))),
+        (None, Some(class_id)) => {
+            Some(Bound::inclusive((ClassId::new(class_id), TokenId::new(""))))
+        }
+        (None, None) => None,
+    };
+    let all: Box<dyn Iterator<Item = StdResult<((String, String), String)>> + '_> = match channel_id
+    {
+        Some(channel_id) => Box::new(class_token_to_channel.idx.channel.prefix(channel_id).range(
+            deps.storage,
+            start,
+            None,
+            Order::Ascending,
+        )),
+        None => Box::new(class_token_to_channel.range(deps.storage, start, None, Order::Ascending)),
+    };
+    // entries are ordered by class ID, so stop at first entry of another class
+    let all = all.take_while(|entry| match (&class_id, entry) {
+        (Some(class_id), Ok(((entry_class_id, _), _))) => entry_class_id == class_id,
+        _ => true,
+    });
+    match limit {
+        Some(limit) => all.take(limit as usize).collect(),
+        None => all.collect(),
+    }
 }
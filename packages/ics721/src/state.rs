@@ -1,14 +1,44 @@
 use cosmwasm_schema::{cw_serde, schemars::JsonSchema};
-use cosmwasm_std::{Addr, Binary, ContractInfoResponse, Empty, Timestamp};
+use cosmwasm_std::{Addr, Binary, ContractInfoResponse, Empty, Timestamp, WasmMsg};
 use cw721::{state::Trait, DefaultOptionalCollectionExtension};
 use cw_pause_once::PauseOrchestrator;
-use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, UniqueIndex};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, UniqueIndex};
 use serde::{Deserialize, Serialize};
 
-use ics721_types::token_types::{Class, ClassId, TokenId};
+use ics721_types::{
+    ibc_types::NonFungibleTokenPacketData,
+    token_types::{Class, ClassId, TokenId},
+    types::Ics721AckResult,
+};
 
 /// The code ID we will use for instantiating new cw721s.
 pub const CW721_CODE_ID: Item<u64> = Item::new("a");
+/// Maps cw721 code ID -> voucher flavour, used for building instantiate and mint msgs
+/// of debt-vouchers. In case of none, `VoucherFlavour::V019` is used.
+pub const CW721_CODE_FLAVOURS: Map<u64, VoucherFlavour> = Map::new("af");
+/// Maps local channel ID -> code ID for instantiating debt-voucher collections received on that channel,
+/// overriding `CW721_CODE_ID`.
+pub const CHANNEL_CW721_CODE_IDS: Map<String, u64> = Map::new("ag");
+/// Maps class ID prefix -> code ID for instantiating debt-voucher collections, overriding channel code IDs
+/// and `CW721_CODE_ID`. A prefix matches at `/` boundaries only, so it either ends with `/` or is a full class ID.
+/// In case of multiple matching prefixes, the longest one is used.
+pub const CLASS_PREFIX_CW721_CODE_IDS: Map<String, u64> = Map::new("ah");
+
+/// Maps class ID -> voucher profile, set by admin or collection owner on home chain for branding
+/// the debt-voucher collection.
+pub const VOUCHER_PROFILES: Map<ClassId, VoucherProfile> = Map::new("ai");
+/// Maps class ID -> local royalty recipient of the debt-voucher collection, claimed by collection owner
+/// on home chain.
+pub const ROYALTY_RECIPIENTS: Map<ClassId, Addr> = Map::new("aj");
+/// Maps class ID -> nonce included in signed royalty recipient proofs, incremented on each update so
+/// proofs can't be replayed.
+pub const ROYALTY_RECIPIENT_NONCES: Map<ClassId, u64> = Map::new("am");
+/// Policy for incoming class and token data in an unknown format. In case of none,
+/// `UnknownMetadataPolicy::StoreRaw` is used.
+pub const UNKNOWN_METADATA_POLICY: Item<UnknownMetadataPolicy> = Item::new("ak");
+/// Maps local channel ID -> whether control packets (e.g. creatorship transfer) are sent and
+/// received on the channel. Only cw-ics721 counterparties understand control packets.
+pub const CONTROL_PACKET_CHANNELS: Map<String, bool> = Map::new("al");
 
 /// The incoming proxy that this contract is handling incoming IbcPackets from, if any.
 pub const INCOMING_PROXY: Item<Option<Addr>> = Item::new("k");
@@ -36,9 +66,27 @@ pub const CLASS_ID_TO_CLASS: Map<ClassId, Class> = Map::new("g");
 
 /// Maps (class ID, token ID) -> local channel ID. Used to determine
 /// the local channel that NFTs have been sent out on.
-pub const OUTGOING_CLASS_TOKEN_TO_CHANNEL: Map<(ClassId, TokenId), String> = Map::new("h");
+pub const OUTGOING_CLASS_TOKEN_TO_CHANNEL: IndexedMap<
+    (ClassId, TokenId),
+    String,
+    ClassTokenToChannelIndexes,
+> = IndexedMap::new(
+    "h",
+    ClassTokenToChannelIndexes {
+        channel: MultiIndex::new(|_pk, channel| channel.clone(), "h", "outgoing__channel"),
+    },
+);
 /// Same as above, but for NFTs arriving at this contract.
-pub const INCOMING_CLASS_TOKEN_TO_CHANNEL: Map<(ClassId, TokenId), String> = Map::new("i");
+pub const INCOMING_CLASS_TOKEN_TO_CHANNEL: IndexedMap<
+    (ClassId, TokenId),
+    String,
+    ClassTokenToChannelIndexes,
+> = IndexedMap::new(
+    "i",
+    ClassTokenToChannelIndexes {
+        channel: MultiIndex::new(|_pk, channel| channel.clone(), "i", "incoming__channel"),
+    },
+);
 
 /// IMPORTANT: collections can either come from (a) smart contracts or (b) nft module.
 /// This map is the truth of source. Only for smart contracts and in case of `receive_nft`
@@ -58,6 +106,82 @@ pub const CW721_ADMIN: Item<Option<Addr>> = Item::new("l");
 /// Bug: https://github.com/CosmWasm/cosmwasm/issues/2155
 pub const CONTRACT_ADDR_LENGTH: Item<u32> = Item::new("n");
 
+/// The result of the incoming packet being processed. It is set on packet receive and
+/// consumed in the reply, where it is provided in the success ack.
+pub const INCOMING_ACK_RESULT: Item<Ics721AckResult> = Item::new("o");
+
+/// Whether NFTs are delivered via `SendNft` to receivers being a contract, even without `send_nft` memo.
+pub const SEND_NFT_TO_CONTRACTS: Item<bool> = Item::new("p");
+
+/// Inbox config. In case of none, inbox mode is disabled.
+pub const INBOX_CONFIG: Item<InboxConfig> = Item::new("q");
+/// Maps (raw receiver, (class ID, token ID)) -> claim for NFTs held in custody by ics721,
+/// since they couldn't be delivered to the receiver. Indexed by expiration, so expired claims
+/// are returned without iterating over all claims.
+pub const CLAIMS: IndexedMap<(String, (ClassId, TokenId)), Claim, ClaimIndexes> = IndexedMap::new(
+    "r",
+    ClaimIndexes {
+        expiration: MultiIndex::new(
+            |_pk, claim| claim.expiration.nanos(),
+            "r",
+            "claims__expiration",
+        ),
+    },
+);
+/// Maps (class ID, token ID) -> receiver and claim of an expired claim being returned to its sender.
+/// In case the return fails or times out, the claim is restored instead of refunding the NFT to ICS721.
+pub const RETURNED_CLAIMS: Map<(ClassId, TokenId), (String, Claim)> = Map::new("an");
+/// In inbox mode, the fallback message in case delivery to receiver fails. It is set on packet
+/// receive and consumed in the reply.
+pub const INBOX_FALLBACK: Item<WasmMsg> = Item::new("s");
+
+/// Maps local channel ID -> whether receivers with a foreign bech32 prefix (e.g. `juno1…`)
+/// are converted to the local prefix (e.g. `stars1…`) on receive.
+pub const CONVERT_RECEIVER_PREFIX: Map<String, bool> = Map::new("t");
+
+/// Optional name service contract, resolving receivers being a name (e.g. `alice.stars`) to an address.
+pub const NAME_SERVICE: Item<Addr> = Item::new("u");
+
+/// Maps (class ID, token ID) -> refund address of outgoing NFTs, given in `IbcOutgoingMsg`.
+pub const OUTGOING_REFUND_ADDRESS: Map<(ClassId, TokenId), Addr> = Map::new("v");
+/// Refunds dispatched on ack fail or timeout, in order of their submessages.
+/// Consumed in the reply, a failed refund is held in custody as a claim for the refund address.
+pub const PENDING_REFUNDS: Item<Vec<PendingRefund>> = Item::new("w");
+
+/// Maps (local channel ID, sequence) -> outgoing packet, dispatched but neither acked nor timed out yet.
+/// Voided packets are kept, so any later ack or timeout is rejected.
+pub const OUTGOING_PACKETS: Map<(String, u64), OutgoingPacket> = Map::new("x");
+/// Outgoing packets dispatched, in order of their submessages. Consumed in the reply, once the sequence is known.
+pub const PENDING_OUTGOING_PACKETS: Item<Vec<OutgoingPacket>> = Item::new("y");
+
+/// Maps local channel ID -> transfer stats.
+pub const CHANNEL_STATS: Map<String, TransferStats> = Map::new("z");
+/// Maps class ID -> transfer stats.
+pub const CLASS_STATS: Map<ClassId, TransferStats> = Map::new("aa");
+
+/// Optional transfer history. In case of none, no history is recorded.
+pub const HISTORY_CONFIG: Item<HistoryConfig> = Item::new("ab");
+/// Maps record ID -> history record, indexed by local address and by (class ID, token ID).
+pub const HISTORY: IndexedMap<u64, HistoryRecord, HistoryIndexes> = IndexedMap::new(
+    "ac",
+    HistoryIndexes {
+        address: MultiIndex::new(
+            |_pk, record| record.address.clone(),
+            "ac",
+            "history__address",
+        ),
+        class_token: MultiIndex::new(
+            |_pk, record| (record.class_id.to_string(), record.token_id.clone().into()),
+            "ac",
+            "history__class_token",
+        ),
+    },
+);
+/// ID of the next history record.
+pub const HISTORY_NEXT_ID: Item<u64> = Item::new("ad");
+/// History records of a received packet, recorded once its submessage succeeded.
+pub const INCOMING_HISTORY: Item<Vec<HistoryRecord>> = Item::new("ae");
+
 #[derive(Deserialize, Debug)]
 pub struct UniversalAllNftInfoResponse {
     pub access: UniversalOwnerOfResponse,
@@ -124,6 +248,146 @@ pub struct UniversalOwnerOfResponse {
     pub approvals: Vec<Empty>,
 }
 
+#[cw_serde]
+pub struct InboxConfig {
+    /// Seconds after which unclaimed NFTs may be returned to sender on source chain.
+    pub claim_expiry: u64,
+    /// IBC timeout in seconds, used for returning expired claims.
+    pub return_timeout: u64,
+}
+
+/// The cw721 version of a code ID, instantiated and minted by ICS721 for debt-vouchers.
+#[cw_serde]
+#[derive(Default)]
+pub enum VoucherFlavour {
+    /// cw721-base v0.16, no on-chain metadata.
+    V016,
+    /// cw721-base v0.17, no on-chain metadata.
+    V017,
+    /// cw721-base v0.18, no on-chain metadata.
+    V018,
+    /// cw721 v0.19 with on-chain metadata, msgs are built by `Ics721Execute::init_msg` and `mint_msg`.
+    #[default]
+    V019,
+}
+
+/// Handling of incoming class and token data, failing to decode by `Ics721Execute::decode_class_data`
+/// and `decode_token_data`. Unknown metadata is never applied to debt-vouchers.
+#[cw_serde]
+#[derive(Default)]
+pub enum UnknownMetadataPolicy {
+    /// Metadata is dropped, so it isn't forwarded on later transfers.
+    Ignore,
+    /// Metadata is stored as is, and forwarded on later transfers.
+    #[default]
+    StoreRaw,
+    /// Transfer is rejected with a fail ack.
+    Reject,
+}
+
+/// Branding of a debt-voucher collection, taking precedence over class data and class ID.
+#[cw_serde]
+#[derive(Default)]
+pub struct VoucherProfile {
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub external_link: Option<String>,
+}
+
+#[cw_serde]
+pub struct HistoryConfig {
+    /// Number of blocks history records are kept, before they may be pruned.
+    pub retention: u64,
+}
+
+#[cw_serde]
+pub struct HistoryRecord {
+    pub action: HistoryAction,
+    pub class_id: ClassId,
+    pub token_id: TokenId,
+    /// The local channel, none for admin actions.
+    pub channel_id: Option<String>,
+    /// The packet sequence, in case it is known.
+    pub sequence: Option<u64>,
+    /// Local address, e.g. sender of outgoing or receiver of incoming NFTs.
+    pub address: String,
+    /// Address on counterparty chain, e.g. receiver of outgoing or sender of incoming NFTs.
+    pub counterparty: Option<String>,
+    pub height: u64,
+    pub outcome: HistoryOutcome,
+}
+
+#[cw_serde]
+pub enum HistoryAction {
+    Send,
+    Receive,
+    Redeem,
+    Refund,
+    AdminBurn,
+    AdminTransfer,
+}
+
+#[cw_serde]
+pub enum HistoryOutcome {
+    /// Packet is in-flight.
+    Pending,
+    Success,
+    Failed {
+        error: String,
+    },
+}
+
+/// Claim for an NFT held in custody by ics721.
+#[cw_serde]
+pub struct Claim {
+    pub nft_contract: Addr,
+    /// The sender on source chain, the NFT is returned to once the claim is expired.
+    pub sender: String,
+    /// The local channel the NFT arrived at.
+    pub channel_id: String,
+    pub expiration: Timestamp,
+}
+
+#[cw_serde]
+pub struct OutgoingPacket {
+    /// The local channel the packet was sent on.
+    pub channel_id: String,
+    pub data: NonFungibleTokenPacketData,
+    /// Whether packet has been recovered by admin, NFTs have been refunded.
+    pub voided: bool,
+}
+
+/// Counters of transferred NFTs. `escrowed` and `vouchered` are the NFTs currently held,
+/// the others are totals.
+#[cw_serde]
+#[derive(Default)]
+pub struct TransferStats {
+    /// NFTs sent out, including refunded ones.
+    pub sent: u64,
+    /// NFTs received, either as voucher or redeemed.
+    pub received: u64,
+    /// Escrowed NFTs returned home.
+    pub redeemed: u64,
+    /// NFTs returned on ack fail, timeout or recovery.
+    pub refunded: u64,
+    /// Home NFTs currently escrowed by ICS721.
+    pub escrowed: u64,
+    /// Vouchers currently minted.
+    pub vouchered: u64,
+}
+
+#[cw_serde]
+pub struct PendingRefund {
+    pub class_id: ClassId,
+    pub token_id: TokenId,
+    pub nft_contract: Addr,
+    pub refund_address: Addr,
+    /// The local channel the NFT was sent on.
+    pub channel_id: String,
+}
+
 /// ClassIdInfo is used to store associated ClassId for given collection/cw721 address.
 #[cw_serde]
 pub struct ClassIdInfo {
@@ -145,6 +409,40 @@ impl<'a> IndexList<ClassIdInfo> for ClassIdInfoIndexes<'a> {
     }
 }
 
+pub struct ClassTokenToChannelIndexes<'a> {
+    pub channel: MultiIndex<'a, String, String, (ClassId, TokenId)>,
+}
+
+impl<'a> IndexList<String> for ClassTokenToChannelIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<String>> + '_> {
+        let v: Vec<&dyn Index<String>> = vec![&self.channel];
+        Box::new(v.into_iter())
+    }
+}
+
+pub struct ClaimIndexes<'a> {
+    pub expiration: MultiIndex<'a, u64, Claim, (String, (ClassId, TokenId))>,
+}
+
+impl<'a> IndexList<Claim> for ClaimIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Claim>> + '_> {
+        let v: Vec<&dyn Index<Claim>> = vec![&self.expiration];
+        Box::new(v.into_iter())
+    }
+}
+
+pub struct HistoryIndexes<'a> {
+    pub address: MultiIndex<'a, String, HistoryRecord, u64>,
+    pub class_token: MultiIndex<'a, (String, String), HistoryRecord, u64>,
+}
+
+impl<'a> IndexList<HistoryRecord> for HistoryIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<HistoryRecord>> + '_> {
+        let v: Vec<&dyn Index<HistoryRecord>> = vec![&self.address, &self.class_token];
+        Box::new(v.into_iter())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::{from_json, to_json_binary};
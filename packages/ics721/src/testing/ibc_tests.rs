@@ -4,28 +4,45 @@ use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
     attr, from_json,
     testing::{mock_dependencies, mock_env, mock_info},
-    to_json_binary, to_json_vec, Addr, Attribute, Binary, DepsMut, Empty, Env, IbcAcknowledgement,
-    IbcChannel, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcEndpoint, IbcOrder, IbcPacket,
-    IbcPacketReceiveMsg, IbcTimeout, Reply, Response, StdResult, SubMsgResponse, SubMsgResult,
-    Timestamp, WasmMsg,
+    to_json_binary, to_json_vec, Addr, Attribute, Binary, ContractResult, CosmosMsg, Deps, DepsMut,
+    Empty, Env, IbcAcknowledgement, IbcChannel, IbcChannelConnectMsg, IbcChannelOpenMsg,
+    IbcEndpoint, IbcOrder, IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg,
+    IbcReceiveResponse, IbcTimeout, QuerierResult, Reply, Response, StdResult, SubMsg,
+    SubMsgResponse, SubMsgResult, SystemError, Timestamp, WasmMsg, WasmQuery,
 };
+use cw_ownable::Ownership;
+use serde::de::DeserializeOwned;
 
 use crate::{
     execute::Ics721Execute,
-    ibc::{Ics721Ibc, ACK_AND_DO_NOTHING_REPLY_ID, IBC_VERSION, INSTANTIATE_CW721_REPLY_ID},
-    ibc_helpers::{ack_fail, ack_success, try_get_ack_error},
-    msg::{CallbackMsg, ExecuteMsg, InstantiateMsg, QueryMsg},
+    hooks::Ics721Hooks,
+    ibc::{
+        Ics721Ibc, ACK_AND_DO_NOTHING_REPLY_ID, ACK_HOOK_REPLY_ID, IBC_VERSION,
+        INSTANTIATE_CW721_REPLY_ID, REFUND_REPLY_ID, SEND_PACKET_REPLY_ID,
+    },
+    ibc_helpers::{ack_fail, ack_success, ack_success_with_result, try_get_ack_error},
+    msg::{CallbackMsg, ExecuteMsg, InstantiateMsg, NameServiceQueryMsg, QueryMsg},
     query::Ics721Query,
-    state::{ClassIdInfo, CollectionData, CLASS_ID_AND_NFT_CONTRACT_INFO, CW721_CODE_ID, PO},
+    state::{
+        ClassIdInfo, CollectionData, InboxConfig, OutgoingPacket, TransferStats, CHANNEL_STATS,
+        CLAIMS, CLASS_ID_AND_NFT_CONTRACT_INFO, CLASS_ID_TO_CLASS, CONTROL_PACKET_CHANNELS,
+        CONVERT_RECEIVER_PREFIX, CW721_CODE_ID, INBOX_CONFIG, INBOX_FALLBACK, INCOMING_ACK_RESULT,
+        INCOMING_CLASS_TOKEN_TO_CHANNEL, NAME_SERVICE, OUTGOING_PACKETS, OUTGOING_REFUND_ADDRESS,
+        PENDING_OUTGOING_PACKETS, PENDING_REFUNDS, PO, SEND_NFT_TO_CONTRACTS,
+    },
     utils::get_collection_data,
     ContractError,
 };
 use ics721_types::{
-    ibc_types::NonFungibleTokenPacketData,
-    token_types::{ClassId, TokenId},
-    types::{Ics721Callbacks, ReceiverExecuteMsg},
+    ibc_types::{Ics721ControlPacket, NonFungibleTokenPacketData},
+    token_types::{Class, ClassId, TokenId},
+    types::{
+        Ics721AckResult, Ics721Callbacks, Ics721ReceiveAction, Ics721Status, ReceiverExecuteMsg,
+    },
 };
 
+use super::unit_tests::ContractInfoResponse;
+
 const CONTRACT_PORT: &str = "wasm.address1";
 const REMOTE_PORT: &str = "stars.address1";
 const CONNECTION_ID: &str = "connection-2";
@@ -45,9 +62,139 @@ impl Ics721Execute<Empty> for Ics721Contract {
         get_collection_data(deps, sender).map(Option::Some)
     }
 }
+impl Ics721Hooks<Empty> for Ics721Contract {}
 impl Ics721Ibc<Empty> for Ics721Contract {}
 impl Ics721Query for Ics721Contract {}
 
+/// Contract vetoing receivers named `blocked`, and adding a message for each hook.
+#[derive(Default)]
+pub struct HookedIcs721Contract {}
+impl Ics721Execute<Empty> for HookedIcs721Contract {
+    type ClassData = CollectionData;
+
+    fn get_class_data(&self, deps: &DepsMut, sender: &Addr) -> StdResult<Option<Self::ClassData>> {
+        get_collection_data(deps, sender).map(Option::Some)
+    }
+}
+impl Ics721Hooks<Empty> for HookedIcs721Contract {
+    fn after_send(
+        &self,
+        _deps: DepsMut,
+        _env: &Env,
+        _channel_id: &str,
+        sequence: Option<u64>,
+        _data: &NonFungibleTokenPacketData,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        Ok(vec![hook_msg(&format!("after_send {sequence:?}"))])
+    }
+
+    fn before_receive(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _packet: &IbcPacket,
+        data: &NonFungibleTokenPacketData,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        if data.receiver == "blocked" {
+            return Err(ContractError::Unauthorized {});
+        }
+        Ok(vec![hook_msg("before_receive")])
+    }
+
+    fn after_receive(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _packet: &IbcPacket,
+        _data: &NonFungibleTokenPacketData,
+        _nft_contract: &Addr,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        Ok(vec![hook_msg("after_receive")])
+    }
+
+    fn on_ack(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _packet: &IbcPacket,
+        _data: &NonFungibleTokenPacketData,
+        _status: &Ics721Status,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        Err(ContractError::Unauthorized {})
+    }
+
+    fn on_timeout(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _packet: &IbcPacket,
+        _data: &NonFungibleTokenPacketData,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        Ok(vec![hook_msg("on_timeout")])
+    }
+}
+impl Ics721Ibc<Empty> for HookedIcs721Contract {}
+
+/// Chain specific message, e.g. for minting via a native NFT module.
+#[cw_serde]
+pub enum ChainMsg {
+    MintNative {
+        class_id: String,
+        token_ids: Vec<String>,
+    },
+}
+
+#[derive(Default)]
+pub struct ChainIcs721Contract {}
+impl Ics721Hooks<ChainMsg> for ChainIcs721Contract {
+    fn after_receive(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _packet: &IbcPacket,
+        data: &NonFungibleTokenPacketData,
+        _nft_contract: &Addr,
+    ) -> Result<Vec<CosmosMsg<ChainMsg>>, ContractError> {
+        Ok(vec![CosmosMsg::Custom(ChainMsg::MintNative {
+            class_id: data.class_id.to_string(),
+            token_ids: data.token_ids.iter().map(|t| t.to_string()).collect(),
+        })])
+    }
+}
+impl Ics721Ibc<ChainMsg> for ChainIcs721Contract {}
+
+fn hook_msg(hook: &str) -> CosmosMsg {
+    CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: "hook".to_string(),
+        msg: to_json_binary(hook).unwrap(),
+        funds: vec![],
+    })
+}
+
+fn conjunction_operands<T>(msg: &CosmosMsg<T>) -> Vec<WasmMsg> {
+    match msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => {
+            match from_json::<ExecuteMsg>(msg).unwrap() {
+                ExecuteMsg::Callback(CallbackMsg::Conjunction { operands }) => operands,
+                _ => panic!("unexpected execute msg"),
+            }
+        }
+        _ => panic!("unexpected cosmos msg"),
+    }
+}
+
+fn hook_messages<T: DeserializeOwned>(msg: &WasmMsg) -> Vec<CosmosMsg<T>> {
+    match msg {
+        WasmMsg::Execute { msg, .. } => match from_json::<ExecuteMsg>(msg).unwrap() {
+            ExecuteMsg::Callback(CallbackMsg::HookMessages { messages }) => {
+                from_json(messages).unwrap()
+            }
+            _ => panic!("unexpected execute msg"),
+        },
+        _ => panic!("unexpected wasm msg"),
+    }
+}
+
 fn mock_channel(channel_id: &str) -> IbcChannel {
     IbcChannel::new(
         IbcEndpoint {
@@ -187,26 +334,969 @@ fn test_reply_cw721() {
 fn test_stateless_reply() {
     let mut deps = mock_dependencies();
 
-    let rep = Reply {
-        id: ACK_AND_DO_NOTHING_REPLY_ID,
-        result: SubMsgResult::Ok(SubMsgResponse {
-            events: vec![],
-            data: None,
-        }),
+    let rep = Reply {
+        id: ACK_AND_DO_NOTHING_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = Ics721Contract::default()
+        .reply(deps.as_mut(), mock_env(), rep)
+        .unwrap();
+    assert_eq!(res.data, Some(ack_success()));
+
+    let rep = Reply {
+        id: ACK_AND_DO_NOTHING_REPLY_ID,
+        result: SubMsgResult::Err("some failure".to_string()),
+    };
+    let res = Ics721Contract::default()
+        .reply(deps.as_mut(), mock_env(), rep)
+        .unwrap();
+    assert_eq!(res.data, Some(ack_fail("some failure".to_string())));
+}
+
+#[test]
+fn test_reply_ack_result() {
+    let mut deps = mock_dependencies();
+    let ack_result = Ics721AckResult {
+        nft_contract: "cosmos2contract".to_string(),
+        action: Ics721ReceiveAction::Mint,
+    };
+
+    // success ack holds result
+    INCOMING_ACK_RESULT
+        .save(deps.as_mut().storage, &ack_result)
+        .unwrap();
+    let rep = Reply {
+        id: ACK_AND_DO_NOTHING_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = Ics721Contract::default()
+        .reply(deps.as_mut(), mock_env(), rep)
+        .unwrap();
+    assert_eq!(res.data, Some(ack_success_with_result(&ack_result)));
+    assert_eq!(INCOMING_ACK_RESULT.may_load(&deps.storage).unwrap(), None);
+
+    // result is dropped on failure
+    INCOMING_ACK_RESULT
+        .save(deps.as_mut().storage, &ack_result)
+        .unwrap();
+    let rep = Reply {
+        id: ACK_AND_DO_NOTHING_REPLY_ID,
+        result: SubMsgResult::Err("some failure".to_string()),
+    };
+    let res = Ics721Contract::default()
+        .reply(deps.as_mut(), mock_env(), rep)
+        .unwrap();
+    assert_eq!(res.data, Some(ack_fail("some failure".to_string())));
+    assert_eq!(INCOMING_ACK_RESULT.may_load(&deps.storage).unwrap(), None);
+}
+
+#[test]
+fn test_packet_timeout_refund() {
+    let mut deps = mock_dependencies();
+    let class_id = ClassId::new("id");
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &class_id,
+            &ClassIdInfo {
+                class_id: class_id.clone(),
+                address: Addr::unchecked("cosmos2contract"),
+            },
+        )
+        .unwrap();
+    // only token 1 has a refund address
+    OUTGOING_REFUND_ADDRESS
+        .save(
+            &mut deps.storage,
+            (class_id.clone(), TokenId::new("1")),
+            &Addr::unchecked("refund"),
+        )
+        .unwrap();
+
+    let data = NonFungibleTokenPacketData {
+        class_id: class_id.clone(),
+        class_uri: None,
+        class_data: None,
+        token_ids: vec![TokenId::new("1"), TokenId::new("2")],
+        token_uris: None,
+        token_data: None,
+        sender: "violet".to_string(),
+        receiver: "blue".to_string(),
+        memo: None,
+    };
+    let res = Ics721Contract::default()
+        .ibc_packet_timeout(
+            deps.as_mut(),
+            mock_env(),
+            IbcPacketTimeoutMsg::new(
+                mock_packet(to_json_binary(&data).unwrap()),
+                Addr::unchecked(RELAYER_ADDR),
+            ),
+        )
+        .unwrap();
+    let refund_msg = |recipient: &str, token_id: &str| {
+        SubMsg::reply_always(
+            WasmMsg::Execute {
+                contract_addr: "cosmos2contract".to_string(),
+                msg: to_json_binary(&cw721_metadata_onchain::msg::ExecuteMsg::TransferNft {
+                    recipient: recipient.to_string(),
+                    token_id: token_id.to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+            REFUND_REPLY_ID,
+        )
+    };
+    assert_eq!(
+        res.messages,
+        vec![refund_msg("refund", "1"), refund_msg("violet", "2")]
+    );
+    assert_eq!(
+        OUTGOING_REFUND_ADDRESS
+            .may_load(&deps.storage, (class_id.clone(), TokenId::new("1")))
+            .unwrap(),
+        None
+    );
+
+    // failed refund is held in custody as a claim
+    let rep = Reply {
+        id: REFUND_REPLY_ID,
+        result: SubMsgResult::Err("cannot receive".to_string()),
+    };
+    Ics721Contract::default()
+        .reply(deps.as_mut(), mock_env(), rep)
+        .unwrap();
+    let claim = CLAIMS
+        .load(
+            &deps.storage,
+            ("refund".to_string(), (class_id.clone(), TokenId::new("1"))),
+        )
+        .unwrap();
+    assert_eq!(claim.nft_contract, Addr::unchecked("cosmos2contract"));
+
+    let rep = Reply {
+        id: REFUND_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    Ics721Contract::default()
+        .reply(deps.as_mut(), mock_env(), rep)
+        .unwrap();
+    assert_eq!(
+        CLAIMS
+            .may_load(
+                &deps.storage,
+                ("violet".to_string(), (class_id, TokenId::new("2"))),
+            )
+            .unwrap(),
+        None
+    );
+    assert_eq!(PENDING_REFUNDS.may_load(&deps.storage).unwrap(), None);
+}
+
+#[test]
+fn test_transfer_stats() {
+    let mut deps = mock_dependencies();
+    let class_id = ClassId::new("id");
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &class_id,
+            &ClassIdInfo {
+                class_id: class_id.clone(),
+                address: Addr::unchecked("cosmos2contract"),
+            },
+        )
+        .unwrap();
+    // simplify: 2 escrowed nfts and 1 voucher sent out
+    CHANNEL_STATS
+        .save(
+            &mut deps.storage,
+            CHANNEL_ID.to_string(),
+            &TransferStats {
+                sent: 3,
+                received: 1,
+                escrowed: 2,
+                vouchered: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    INCOMING_CLASS_TOKEN_TO_CHANNEL
+        .save(
+            &mut deps.storage,
+            (class_id.clone(), TokenId::new("3")),
+            &CHANNEL_ID.to_string(),
+        )
+        .unwrap();
+    let packet_data = |token_ids: Vec<&str>| NonFungibleTokenPacketData {
+        class_id: class_id.clone(),
+        class_uri: None,
+        class_data: None,
+        token_ids: token_ids.into_iter().map(TokenId::new).collect(),
+        token_uris: None,
+        token_data: None,
+        sender: "violet".to_string(),
+        receiver: "blue".to_string(),
+        memo: None,
+    };
+
+    // refunded nfts aren't escrowed anymore
+    Ics721Contract::default()
+        .ibc_packet_timeout(
+            deps.as_mut(),
+            mock_env(),
+            IbcPacketTimeoutMsg::new(
+                mock_packet(to_json_binary(&packet_data(vec!["1", "2"])).unwrap()),
+                Addr::unchecked(RELAYER_ADDR),
+            ),
+        )
+        .unwrap();
+    // voucher is burned on ack success
+    Ics721Contract::default()
+        .ibc_packet_ack(
+            deps.as_mut(),
+            mock_env(),
+            IbcPacketAckMsg::new(
+                IbcAcknowledgement::new(ack_success()),
+                mock_packet(to_json_binary(&packet_data(vec!["3"])).unwrap()),
+                Addr::unchecked(RELAYER_ADDR),
+            ),
+        )
+        .unwrap();
+
+    let expected = TransferStats {
+        sent: 3,
+        received: 1,
+        redeemed: 0,
+        refunded: 2,
+        escrowed: 0,
+        vouchered: 0,
+    };
+    let query_stats = |channel_id: Option<&str>, class_id: Option<&str>| {
+        Ics721Contract::default()
+            .query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Stats {
+                    channel_id: channel_id.map(str::to_string),
+                    class_id: class_id.map(str::to_string),
+                },
+            )
+            .and_then(|binary| Ok(from_json::<TransferStats>(binary)?))
+    };
+    assert_eq!(query_stats(Some(CHANNEL_ID), None).unwrap(), expected);
+    assert_eq!(query_stats(None, None).unwrap(), expected);
+    // class stats are only updated for transfers, not by seeded channel stats
+    assert_eq!(
+        query_stats(None, Some("id")).unwrap(),
+        TransferStats {
+            refunded: 2,
+            ..Default::default()
+        }
+    );
+    query_stats(Some(CHANNEL_ID), Some("id")).unwrap_err();
+}
+
+#[test]
+fn test_recover_packet() {
+    let mut deps = mock_dependencies();
+    PO.set_pauser(&mut deps.storage, &deps.api, None).unwrap();
+    let class_id = ClassId::new("id");
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &class_id,
+            &ClassIdInfo {
+                class_id: class_id.clone(),
+                address: Addr::unchecked("cosmos2contract"),
+            },
+        )
+        .unwrap();
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::ContractInfo { .. } => QuerierResult::Ok(ContractResult::Ok(
+            to_json_binary(&ContractInfoResponse {
+                code_id: 1,
+                creator: "creator".to_string(),
+                admin: Some("admin".to_string()),
+                pinned: false,
+                ibc_port: None,
+            })
+            .unwrap(),
+        )),
+        _ => QuerierResult::Err(SystemError::NoSuchContract {
+            addr: "unknown".to_string(),
+        }),
+    });
+
+    // packet is recorded in reply, once sequence is known
+    let data = NonFungibleTokenPacketData {
+        class_id: class_id.clone(),
+        class_uri: None,
+        class_data: None,
+        token_ids: vec![TokenId::new("1")],
+        token_uris: None,
+        token_data: None,
+        sender: "violet".to_string(),
+        receiver: "blue".to_string(),
+        memo: None,
+    };
+    let outgoing_packet = OutgoingPacket {
+        channel_id: CHANNEL_ID.to_string(),
+        data: data.clone(),
+        voided: false,
+    };
+    PENDING_OUTGOING_PACKETS
+        .save(&mut deps.storage, &vec![outgoing_packet.clone()])
+        .unwrap();
+    let rep = Reply {
+        id: SEND_PACKET_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            // MsgIbcSendResponse { sequence: 42 }
+            data: Some(Binary::from([0x08, 0x2a])),
+        }),
+    };
+    Ics721Contract::default()
+        .reply(deps.as_mut(), mock_env(), rep)
+        .unwrap();
+    assert_eq!(
+        OUTGOING_PACKETS
+            .load(&deps.storage, (CHANNEL_ID.to_string(), 42))
+            .unwrap(),
+        outgoing_packet
+    );
+    assert_eq!(
+        PENDING_OUTGOING_PACKETS.may_load(&deps.storage).unwrap(),
+        None
+    );
+
+    let recover = |deps: DepsMut, sender: &str| {
+        Ics721Contract::default().execute(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::RecoverPacket {
+                channel: CHANNEL_ID.to_string(),
+                sequence: 42,
+            },
+        )
+    };
+    // only admin can recover
+    let err = recover(deps.as_mut(), "bob").unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = recover(deps.as_mut(), "admin").unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_always(
+            WasmMsg::Execute {
+                contract_addr: "cosmos2contract".to_string(),
+                msg: to_json_binary(&cw721_metadata_onchain::msg::ExecuteMsg::TransferNft {
+                    recipient: "violet".to_string(),
+                    token_id: "1".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+            REFUND_REPLY_ID,
+        )]
+    );
+    assert!(
+        OUTGOING_PACKETS
+            .load(&deps.storage, (CHANNEL_ID.to_string(), 42))
+            .unwrap()
+            .voided
+    );
+
+    // packet can't be recovered twice
+    let err = recover(deps.as_mut(), "admin").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PacketVoided {
+            channel: CHANNEL_ID.to_string(),
+            sequence: 42
+        }
+    );
+
+    // later ack is rejected
+    let err = Ics721Contract::default()
+        .ibc_packet_ack(
+            deps.as_mut(),
+            mock_env(),
+            IbcPacketAckMsg::new(
+                IbcAcknowledgement::new(ack_success()),
+                mock_packet(to_json_binary(&data).unwrap()),
+                Addr::unchecked(RELAYER_ADDR),
+            ),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PacketVoided {
+            channel: CHANNEL_ID.to_string(),
+            sequence: 42
+        }
+    );
+}
+
+#[test]
+fn test_send_packet_reply_without_sequence() {
+    let mut deps = mock_dependencies();
+    let data = NonFungibleTokenPacketData {
+        class_id: ClassId::new("id"),
+        class_uri: None,
+        class_data: None,
+        token_ids: vec![TokenId::new("1")],
+        token_uris: None,
+        token_data: None,
+        sender: "violet".to_string(),
+        receiver: "blue".to_string(),
+        memo: None,
+    };
+    PENDING_OUTGOING_PACKETS
+        .save(
+            &mut deps.storage,
+            &vec![OutgoingPacket {
+                channel_id: CHANNEL_ID.to_string(),
+                data,
+                voided: false,
+            }],
+        )
+        .unwrap();
+    // older chains don't return `MsgIbcSendResponse` data
+    let rep = Reply {
+        id: SEND_PACKET_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = Ics721Contract::default()
+        .reply(deps.as_mut(), mock_env(), rep)
+        .unwrap();
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("recoverable", "false")));
+    assert_eq!(
+        PENDING_OUTGOING_PACKETS.may_load(&deps.storage).unwrap(),
+        None
+    );
+    assert!(OUTGOING_PACKETS.is_empty(&deps.storage));
+}
+
+#[test]
+fn test_ibc_packet_receive_transfer_creatorship() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    PO.set_pauser(&mut deps.storage, &deps.api, None).unwrap();
+    // voucher collection of home class ID `collection`
+    let class_id = ClassId::new(format!("{CONTRACT_PORT}/{CHANNEL_ID}/collection"));
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &class_id,
+            &ClassIdInfo {
+                class_id: class_id.clone(),
+                address: Addr::unchecked("nftcontract"),
+            },
+        )
+        .unwrap();
+    CLASS_ID_TO_CLASS
+        .save(
+            &mut deps.storage,
+            class_id.clone(),
+            &Class {
+                id: class_id.clone(),
+                uri: None,
+                data: None,
+            },
+        )
+        .unwrap();
+    let set_creator = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, creator: &str| {
+        let creator = creator.to_string();
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { .. } => QuerierResult::Ok(ContractResult::Ok(
+                to_json_binary(&Ownership::<Addr> {
+                    owner: Some(Addr::unchecked(creator.clone())),
+                    pending_owner: None,
+                    pending_expiry: None,
+                })
+                .unwrap(),
+            )),
+            _ => QuerierResult::Err(SystemError::NoSuchContract {
+                addr: "unknown".to_string(),
+            }),
+        });
+    };
+    let class_data = to_json_binary(&CollectionData {
+        owner: Some("owner".to_string()),
+        contract_info: None,
+        num_tokens: Some(1),
+        name: "name".to_string(),
+        symbol: "symbol".to_string(),
+        extension: None,
+    })
+    .unwrap();
+    let packet = |sender: &str| {
+        mock_packet(
+            to_json_binary(&Ics721ControlPacket::TransferCreatorship {
+                class_id: ClassId::new("collection"),
+                class_data: class_data.clone(),
+                sender: sender.to_string(),
+                new_creator: Some("creator".to_string()),
+            })
+            .unwrap(),
+        )
+    };
+
+    // control packets must be enabled on channel
+    let res = Ics721Contract::default()
+        .ibc_packet_receive(
+            deps.as_mut(),
+            env.clone(),
+            IbcPacketReceiveMsg::new(packet("owner"), Addr::unchecked(RELAYER_ADDR)),
+        )
+        .unwrap();
+    assert!(try_get_ack_error(&IbcAcknowledgement::new(res.acknowledgement)).is_some());
+    assert!(res.messages.is_empty());
+    CONTROL_PACKET_CHANNELS
+        .save(&mut deps.storage, CHANNEL_ID.to_string(), &true)
+        .unwrap();
+
+    // ics721 must be creator of voucher collection
+    set_creator(&mut deps, "admin");
+    let res = Ics721Contract::default()
+        .ibc_packet_receive(
+            deps.as_mut(),
+            env.clone(),
+            IbcPacketReceiveMsg::new(packet("owner"), Addr::unchecked(RELAYER_ADDR)),
+        )
+        .unwrap();
+    assert_eq!(
+        try_get_ack_error(&IbcAcknowledgement::new(res.acknowledgement)),
+        Some(ContractError::NotCreator("nftcontract".to_string()).to_string())
+    );
+
+    // only collection owner can transfer creatorship
+    set_creator(&mut deps, env.contract.address.as_str());
+    let res = Ics721Contract::default()
+        .ibc_packet_receive(
+            deps.as_mut(),
+            env.clone(),
+            IbcPacketReceiveMsg::new(packet("bob"), Addr::unchecked(RELAYER_ADDR)),
+        )
+        .unwrap();
+    assert_eq!(
+        try_get_ack_error(&IbcAcknowledgement::new(res.acknowledgement)),
+        Some(
+            ContractError::NotCollectionOwner {
+                sender: "bob".to_string(),
+                class_id: "collection".to_string(),
+            }
+            .to_string()
+        )
+    );
+
+    let res = Ics721Contract::default()
+        .ibc_packet_receive(
+            deps.as_mut(),
+            env.clone(),
+            IbcPacketReceiveMsg::new(packet("owner"), Addr::unchecked(RELAYER_ADDR)),
+        )
+        .unwrap();
+    assert_eq!(res.acknowledgement, ack_success());
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "nftcontract".to_string(),
+            msg: to_json_binary(
+                &cw721_metadata_onchain::msg::ExecuteMsg::UpdateCreatorOwnership(
+                    cw_ownable::Action::TransferOwnership {
+                        new_owner: "creator".to_string(),
+                        expiry: None,
+                    },
+                ),
+            )
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+    // class data is updated
+    assert_eq!(
+        CLASS_ID_TO_CLASS
+            .load(&deps.storage, class_id)
+            .unwrap()
+            .data,
+        Some(class_data.clone())
+    );
+
+    // no refunds for control packets on ack fail or timeout
+    let res = Ics721Contract::default()
+        .ibc_packet_timeout(
+            deps.as_mut(),
+            env.clone(),
+            IbcPacketTimeoutMsg::new(packet("owner"), Addr::unchecked(RELAYER_ADDR)),
+        )
+        .unwrap();
+    assert!(res.messages.is_empty());
+    assert!(res.attributes.contains(&attr("status", "timeout")));
+
+    // control packets in flight are still handled, once control packets are disabled
+    CONTROL_PACKET_CHANNELS
+        .save(&mut deps.storage, CHANNEL_ID.to_string(), &false)
+        .unwrap();
+    let res = Ics721Contract::default()
+        .ibc_packet_ack(
+            deps.as_mut(),
+            env.clone(),
+            IbcPacketAckMsg::new(
+                IbcAcknowledgement::new(ack_fail("fail".to_string())),
+                packet("owner"),
+                Addr::unchecked(RELAYER_ADDR),
+            ),
+        )
+        .unwrap();
+    assert!(res.messages.is_empty());
+    assert!(res.attributes.contains(&attr("status", "failed")));
+    let res = Ics721Contract::default()
+        .ibc_packet_timeout(
+            deps.as_mut(),
+            env,
+            IbcPacketTimeoutMsg::new(packet("owner"), Addr::unchecked(RELAYER_ADDR)),
+        )
+        .unwrap();
+    assert!(res.attributes.contains(&attr("status", "timeout")));
+}
+
+#[test]
+fn test_hooks() {
+    let mut deps = mock_dependencies();
+    PO.set_pauser(&mut deps.storage, &deps.api, None).unwrap();
+    CW721_CODE_ID
+        .save(&mut deps.storage, &CW721_BASE_CODE_ID)
+        .unwrap();
+    let dest_class_id = ClassId::new(format!("{CONTRACT_PORT}/{CHANNEL_ID}/id"));
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &dest_class_id,
+            &ClassIdInfo {
+                class_id: dest_class_id.clone(),
+                address: Addr::unchecked("cosmos2contract"),
+            },
+        )
+        .unwrap();
+    let packet_data = |class_id: &str, receiver: &str| NonFungibleTokenPacketData {
+        class_id: ClassId::new(class_id),
+        class_uri: None,
+        class_data: None,
+        token_ids: vec![TokenId::new("1")],
+        token_uris: None,
+        token_data: None,
+        sender: "violet".to_string(),
+        receiver: receiver.to_string(),
+        memo: None,
+    };
+    let receive = |deps: DepsMut, receiver: &str| {
+        HookedIcs721Contract::default()
+            .ibc_packet_receive(
+                deps,
+                mock_env(),
+                IbcPacketReceiveMsg::new(
+                    mock_packet(to_json_binary(&packet_data("id", receiver)).unwrap()),
+                    Addr::unchecked(RELAYER_ADDR),
+                ),
+            )
+            .unwrap()
+    };
+
+    // before receive hook vetoes transfer
+    let res = receive(deps.as_mut(), "blocked");
+    assert_eq!(
+        try_get_ack_error(&IbcAcknowledgement::new(res.acknowledgement)),
+        Some(ContractError::Unauthorized {}.to_string())
+    );
+    assert!(res.messages.is_empty());
+
+    // hook messages are executed before and after vouchers are created, within the receive submessage
+    let res = receive(deps.as_mut(), "blue");
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, ACK_AND_DO_NOTHING_REPLY_ID);
+    let operands = conjunction_operands(&res.messages[0].msg);
+    assert_eq!(operands.len(), 3);
+    assert_eq!(
+        hook_messages::<Empty>(&operands[0]),
+        vec![hook_msg("before_receive")]
+    );
+    assert_eq!(
+        hook_messages::<Empty>(&operands[2]),
+        vec![hook_msg("after_receive")]
+    );
+
+    // after send hook is called once sequence is known
+    PENDING_OUTGOING_PACKETS
+        .save(
+            &mut deps.storage,
+            &vec![OutgoingPacket {
+                channel_id: CHANNEL_ID.to_string(),
+                data: packet_data("id", "blue"),
+                voided: false,
+            }],
+        )
+        .unwrap();
+    let res = HookedIcs721Contract::default()
+        .reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: SEND_PACKET_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: Some(Binary::from([0x08, 0x2a])),
+                }),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].msg, hook_msg("after_send Some(42)"));
+
+    // timeout hook message is added after refunds, failures are ignored
+    let class_id = ClassId::new("id");
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &class_id,
+            &ClassIdInfo {
+                class_id: class_id.clone(),
+                address: Addr::unchecked("cosmos2contract"),
+            },
+        )
+        .unwrap();
+    let res = HookedIcs721Contract::default()
+        .ibc_packet_timeout(
+            deps.as_mut(),
+            mock_env(),
+            IbcPacketTimeoutMsg::new(
+                mock_packet(to_json_binary(&packet_data("id", "blue")).unwrap()),
+                Addr::unchecked(RELAYER_ADDR),
+            ),
+        )
+        .unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(res.messages[0].id, REFUND_REPLY_ID);
+    assert_eq!(
+        res.messages[1],
+        SubMsg::reply_on_error(hook_msg("on_timeout"), ACK_HOOK_REPLY_ID)
+    );
+    let res = HookedIcs721Contract::default()
+        .reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: ACK_HOOK_REPLY_ID,
+                result: SubMsgResult::Err("hook failed".to_string()),
+            },
+        )
+        .unwrap();
+    assert!(res.attributes.contains(&attr("hook_error", "hook failed")));
+
+    // ack hook error doesn't prevent refunds
+    let res = HookedIcs721Contract::default()
+        .ibc_packet_ack(
+            deps.as_mut(),
+            mock_env(),
+            IbcPacketAckMsg::new(
+                IbcAcknowledgement::new(ack_fail("fail".to_string())),
+                mock_packet(to_json_binary(&packet_data("id", "blue")).unwrap()),
+                Addr::unchecked(RELAYER_ADDR),
+            ),
+        )
+        .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, REFUND_REPLY_ID);
+    assert!(res.attributes.contains(&attr(
+        "hook_error",
+        ContractError::Unauthorized {}.to_string()
+    )));
+}
+
+#[test]
+fn test_custom_msg() {
+    let mut deps = mock_dependencies();
+    PO.set_pauser(&mut deps.storage, &deps.api, None).unwrap();
+    CW721_CODE_ID
+        .save(&mut deps.storage, &CW721_BASE_CODE_ID)
+        .unwrap();
+    let dest_class_id = ClassId::new(format!("{CONTRACT_PORT}/{CHANNEL_ID}/id"));
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &dest_class_id,
+            &ClassIdInfo {
+                class_id: dest_class_id.clone(),
+                address: Addr::unchecked("cosmos2contract"),
+            },
+        )
+        .unwrap();
+    let data = NonFungibleTokenPacketData {
+        class_id: ClassId::new("id"),
+        class_uri: None,
+        class_data: None,
+        token_ids: vec![TokenId::new("1")],
+        token_uris: None,
+        token_data: None,
+        sender: "violet".to_string(),
+        receiver: "blue".to_string(),
+        memo: None,
+    };
+
+    // custom messages are carried through receive
+    let res = ChainIcs721Contract::default()
+        .ibc_packet_receive(
+            deps.as_mut(),
+            mock_env(),
+            IbcPacketReceiveMsg::new(
+                mock_packet(to_json_binary(&data).unwrap()),
+                Addr::unchecked(RELAYER_ADDR),
+            ),
+        )
+        .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, ACK_AND_DO_NOTHING_REPLY_ID);
+    let operands = conjunction_operands(&res.messages[0].msg);
+    assert_eq!(operands.len(), 2);
+    assert_eq!(
+        hook_messages::<ChainMsg>(&operands[1]),
+        vec![CosmosMsg::Custom(ChainMsg::MintNative {
+            class_id: "id".to_string(),
+            token_ids: vec!["1".to_string()],
+        })]
+    );
+}
+
+#[test]
+fn test_ibc_packet_receive_receiver_conversion() {
+    let mut deps = mock_dependencies();
+    CW721_CODE_ID
+        .save(&mut deps.storage, &CW721_BASE_CODE_ID)
+        .unwrap();
+    let dest_class_id = format!("{}/{}/{}", CONTRACT_PORT, CHANNEL_ID, "id");
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &ClassId::new(dest_class_id.clone()),
+            &ClassIdInfo {
+                class_id: ClassId::new(dest_class_id),
+                address: Addr::unchecked("cosmos2contract"),
+            },
+        )
+        .unwrap();
+    PO.set_pauser(&mut deps.storage, &deps.api, None).unwrap();
+
+    // conversion uses the bech32 prefix of the ics721 contract
+    let bech32 = |hrp: &str, data: &[u8]| {
+        bech32::encode::<bech32::Bech32>(bech32::Hrp::parse(hrp).unwrap(), data).unwrap()
     };
-    let res = Ics721Contract::default()
-        .reply(deps.as_mut(), mock_env(), rep)
+    let mut env = mock_env();
+    env.contract.address = Addr::unchecked(bech32("stars", &[2; 20]));
+    // uppercase bech32 is valid, but not a normalized (local) address
+    let foreign_receiver = bech32("juno", &[1; 20]).to_uppercase();
+    let converted_receiver = bech32("stars", &[1; 20]);
+
+    let receive = |deps: DepsMut, receiver: &str| {
+        let data = NonFungibleTokenPacketData {
+            class_id: ClassId::new("id"),
+            class_uri: None,
+            class_data: None,
+            token_ids: vec![TokenId::new("1")],
+            token_uris: None,
+            token_data: None,
+            sender: "violet".to_string(),
+            receiver: receiver.to_string(),
+            memo: None,
+        };
+        let packet = IbcPacketReceiveMsg::new(
+            mock_packet(to_json_binary(&data).unwrap()),
+            Addr::unchecked(RELAYER_ADDR),
+        );
+        Ics721Contract::default()
+            .ibc_packet_receive(deps, env.clone(), packet)
+            .unwrap()
+    };
+    let operands = |res: &IbcReceiveResponse| match res.messages[0].msg.clone() {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => {
+            match from_json::<ExecuteMsg>(msg).unwrap() {
+                ExecuteMsg::Callback(CallbackMsg::Conjunction { operands }) => operands,
+                _ => panic!("unexpected execute msg"),
+            }
+        }
+        _ => panic!("unexpected cosmos msg"),
+    };
+
+    // conversion is disabled by default
+    let res = receive(deps.as_mut(), &foreign_receiver);
+    assert!(try_get_ack_error(&IbcAcknowledgement::new(res.acknowledgement)).is_some());
+
+    // vouchers are minted to the converted receiver
+    CONVERT_RECEIVER_PREFIX
+        .save(&mut deps.storage, CHANNEL_ID.to_string(), &true)
         .unwrap();
-    assert_eq!(res.data, Some(ack_success()));
+    let res = receive(deps.as_mut(), &foreign_receiver);
+    match operands(&res)[0].clone() {
+        WasmMsg::Execute { msg, .. } => match from_json::<ExecuteMsg>(msg).unwrap() {
+            ExecuteMsg::Callback(CallbackMsg::CreateVouchers { receiver, .. }) => {
+                assert_eq!(receiver, converted_receiver)
+            }
+            _ => panic!("unexpected execute msg"),
+        },
+        _ => panic!("unexpected wasm msg"),
+    }
 
-    let rep = Reply {
-        id: ACK_AND_DO_NOTHING_REPLY_ID,
-        result: SubMsgResult::Err("some failure".to_string()),
-    };
-    let res = Ics721Contract::default()
-        .reply(deps.as_mut(), mock_env(), rep)
+    // conversion fails for addresses not holding 20 or 32 bytes
+    let unconvertible_receiver = bech32("juno", &[1; 10]).to_uppercase();
+    let res = receive(deps.as_mut(), &unconvertible_receiver);
+    assert!(try_get_ack_error(&IbcAcknowledgement::new(res.acknowledgement)).is_some());
+
+    // in inbox mode, NFTs are held in custody and claimable by the raw receiver
+    INBOX_CONFIG
+        .save(
+            &mut deps.storage,
+            &InboxConfig {
+                claim_expiry: 3600,
+                return_timeout: 600,
+            },
+        )
         .unwrap();
-    assert_eq!(res.data, Some(ack_fail("some failure".to_string())));
+    let res = receive(deps.as_mut(), &unconvertible_receiver);
+    let operands = operands(&res);
+    match operands[0].clone() {
+        WasmMsg::Execute { msg, .. } => match from_json::<ExecuteMsg>(msg).unwrap() {
+            ExecuteMsg::Callback(CallbackMsg::CreateVouchers { receiver, .. }) => {
+                assert_eq!(receiver, env.contract.address.to_string())
+            }
+            _ => panic!("unexpected execute msg"),
+        },
+        _ => panic!("unexpected wasm msg"),
+    }
+    match operands[1].clone() {
+        WasmMsg::Execute { msg, .. } => match from_json::<ExecuteMsg>(msg).unwrap() {
+            ExecuteMsg::Callback(CallbackMsg::AddClaims { receiver, .. }) => {
+                assert_eq!(receiver, unconvertible_receiver)
+            }
+            _ => panic!("unexpected execute msg"),
+        },
+        _ => panic!("unexpected wasm msg"),
+    }
 }
 
 #[test]
@@ -474,6 +1564,14 @@ fn test_ibc_packet_receive() {
         .unwrap();
     // assert there is only one message
     assert_eq!(response.messages.len(), 1);
+    // ack result is provided in reply
+    assert_eq!(
+        INCOMING_ACK_RESULT.load(&deps.storage).unwrap(),
+        Ics721AckResult {
+            nft_contract: "cosmos2contract".to_string(),
+            action: Ics721ReceiveAction::Mint,
+        }
+    );
 
     let conjunction_msg = match response.messages[0].msg.clone() {
         cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => {
@@ -526,6 +1624,440 @@ fn test_ibc_packet_receive() {
     }
 }
 
+#[test]
+fn test_ibc_packet_receive_wasm_hook() {
+    let mut deps = mock_dependencies();
+    CW721_CODE_ID
+        .save(&mut deps.storage, &CW721_BASE_CODE_ID)
+        .unwrap();
+    let dest_class_id = format!("{}/{}/{}", CONTRACT_PORT, CHANNEL_ID, "id");
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &ClassId::new(dest_class_id.clone()),
+            &ClassIdInfo {
+                class_id: ClassId::new(dest_class_id),
+                address: Addr::unchecked("cosmos2contract"),
+            },
+        )
+        .unwrap();
+    PO.set_pauser(&mut deps.storage, &deps.api, None).unwrap();
+
+    let hook_msg = to_json_binary("list").unwrap();
+    let packet_data = |receiver: &str| NonFungibleTokenPacketData {
+        class_id: ClassId::new("id"),
+        class_uri: None,
+        class_data: None,
+        token_ids: vec![TokenId::new("1")],
+        token_uris: None,
+        token_data: None,
+        sender: "violet".to_string(),
+        receiver: receiver.to_string(),
+        memo: Some(format!(
+            r#"{{"wasm":{{"contract":"marketplace","msg":"{}"}}}}"#,
+            hook_msg.to_base64()
+        )),
+    };
+
+    // hook contract must be the receiver
+    let packet = IbcPacketReceiveMsg::new(
+        mock_packet(to_json_binary(&packet_data("blue")).unwrap()),
+        Addr::unchecked(RELAYER_ADDR),
+    );
+    let res = Ics721Contract::default()
+        .ibc_packet_receive(deps.as_mut(), mock_env(), packet)
+        .unwrap();
+    let error = try_get_ack_error(&IbcAcknowledgement::new(res.acknowledgement));
+    assert_eq!(
+        error,
+        Some(
+            ContractError::WasmHookReceiverMismatch {
+                receiver: "blue".to_string(),
+                contract: "marketplace".to_string(),
+            }
+            .to_string()
+        )
+    );
+
+    // vouchers are minted to ics721 and then sent to hook contract
+    let packet = IbcPacketReceiveMsg::new(
+        mock_packet(to_json_binary(&packet_data("marketplace")).unwrap()),
+        Addr::unchecked(RELAYER_ADDR),
+    );
+    let env = mock_env();
+    let res = Ics721Contract::default()
+        .ibc_packet_receive(deps.as_mut(), env.clone(), packet)
+        .unwrap();
+    let operands = match res.messages[0].msg.clone() {
+        cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => {
+            match from_json::<ExecuteMsg>(msg).unwrap() {
+                ExecuteMsg::Callback(CallbackMsg::Conjunction { operands }) => operands,
+                _ => panic!("unexpected execute msg"),
+            }
+        }
+        _ => panic!("unexpected cosmos msg"),
+    };
+    assert_eq!(operands.len(), 3);
+    match operands[0].clone() {
+        WasmMsg::Execute { msg, .. } => match from_json::<ExecuteMsg>(msg).unwrap() {
+            ExecuteMsg::Callback(CallbackMsg::CreateVouchers { receiver, .. }) => {
+                assert_eq!(receiver, env.contract.address.to_string())
+            }
+            _ => panic!("unexpected execute msg"),
+        },
+        _ => panic!("unexpected wasm msg"),
+    }
+    assert_eq!(
+        operands[1],
+        WasmMsg::Execute {
+            contract_addr: "cosmos2contract".to_string(),
+            msg: to_json_binary(&cw721_metadata_onchain::msg::ExecuteMsg::SendNft {
+                contract: "marketplace".to_string(),
+                token_id: "1".to_string(),
+                msg: hook_msg,
+            })
+            .unwrap(),
+            funds: vec![],
+        }
+    );
+}
+
+#[test]
+fn test_ibc_packet_receive_send_nft() {
+    let mut deps = mock_dependencies();
+    CW721_CODE_ID
+        .save(&mut deps.storage, &CW721_BASE_CODE_ID)
+        .unwrap();
+    let dest_class_id = format!("{}/{}/{}", CONTRACT_PORT, CHANNEL_ID, "id");
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &ClassId::new(dest_class_id.clone()),
+            &ClassIdInfo {
+                class_id: ClassId::new(dest_class_id),
+                address: Addr::unchecked("cosmos2contract"),
+            },
+        )
+        .unwrap();
+    PO.set_pauser(&mut deps.storage, &deps.api, None).unwrap();
+    // only "vault" is a contract
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::ContractInfo { contract_addr } if contract_addr == "vault" => {
+            QuerierResult::Ok(ContractResult::Ok(
+                to_json_binary(&ContractInfoResponse {
+                    code_id: 1,
+                    creator: "creator".to_string(),
+                    admin: None,
+                    pinned: false,
+                    ibc_port: None,
+                })
+                .unwrap(),
+            ))
+        }
+        _ => QuerierResult::Err(SystemError::NoSuchContract {
+            addr: "unknown".to_string(),
+        }),
+    });
+
+    let receive = |deps: DepsMut, receiver: &str, memo: Option<String>| {
+        let data = NonFungibleTokenPacketData {
+            class_id: ClassId::new("id"),
+            class_uri: None,
+            class_data: None,
+            token_ids: vec![TokenId::new("1")],
+            token_uris: None,
+            token_data: None,
+            sender: "violet".to_string(),
+            receiver: receiver.to_string(),
+            memo,
+        };
+        let packet = IbcPacketReceiveMsg::new(
+            mock_packet(to_json_binary(&data).unwrap()),
+            Addr::unchecked(RELAYER_ADDR),
+        );
+        let res = Ics721Contract::default()
+            .ibc_packet_receive(deps, mock_env(), packet)
+            .unwrap();
+        match res.messages[0].msg.clone() {
+            cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => {
+                match from_json::<ExecuteMsg>(msg).unwrap() {
+                    ExecuteMsg::Callback(CallbackMsg::Conjunction { operands }) => operands,
+                    _ => panic!("unexpected execute msg"),
+                }
+            }
+            _ => panic!("unexpected cosmos msg"),
+        }
+    };
+    let send_nft_msg = |contract: &str, msg: Binary| WasmMsg::Execute {
+        contract_addr: "cosmos2contract".to_string(),
+        msg: to_json_binary(&cw721_metadata_onchain::msg::ExecuteMsg::SendNft {
+            contract: contract.to_string(),
+            token_id: "1".to_string(),
+            msg,
+        })
+        .unwrap(),
+        funds: vec![],
+    };
+
+    // opt-in by memo
+    let operands = receive(
+        deps.as_mut(),
+        "blue",
+        Some(r#"{"send_nft":{"msg":"ImRlcG9zaXQi"}}"#.to_string()),
+    );
+    assert_eq!(operands.len(), 3);
+    assert_eq!(
+        operands[1],
+        send_nft_msg("blue", to_json_binary("deposit").unwrap())
+    );
+
+    // contract receiver is not auto-detected by default
+    let operands = receive(deps.as_mut(), "vault", None);
+    assert_eq!(operands.len(), 2);
+
+    // auto-detect contract receiver
+    SEND_NFT_TO_CONTRACTS
+        .save(&mut deps.storage, &true)
+        .unwrap();
+    let operands = receive(deps.as_mut(), "vault", None);
+    assert_eq!(operands.len(), 3);
+    assert_eq!(operands[1], send_nft_msg("vault", Binary::default()));
+
+    // no contract
+    let operands = receive(deps.as_mut(), "blue", None);
+    assert_eq!(operands.len(), 2);
+}
+
+#[test]
+fn test_ibc_packet_receive_inbox() {
+    let mut deps = mock_dependencies();
+    CW721_CODE_ID
+        .save(&mut deps.storage, &CW721_BASE_CODE_ID)
+        .unwrap();
+    let dest_class_id = format!("{}/{}/{}", CONTRACT_PORT, CHANNEL_ID, "id");
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &ClassId::new(dest_class_id.clone()),
+            &ClassIdInfo {
+                class_id: ClassId::new(dest_class_id),
+                address: Addr::unchecked("cosmos2contract"),
+            },
+        )
+        .unwrap();
+    PO.set_pauser(&mut deps.storage, &deps.api, None).unwrap();
+    INBOX_CONFIG
+        .save(
+            &mut deps.storage,
+            &InboxConfig {
+                claim_expiry: 3600,
+                return_timeout: 600,
+            },
+        )
+        .unwrap();
+
+    let receive = |deps: DepsMut, receiver: &str, memo: Option<String>| {
+        let data = NonFungibleTokenPacketData {
+            class_id: ClassId::new("id"),
+            class_uri: None,
+            class_data: None,
+            token_ids: vec![TokenId::new("1")],
+            token_uris: None,
+            token_data: None,
+            sender: "violet".to_string(),
+            receiver: receiver.to_string(),
+            memo,
+        };
+        let packet = IbcPacketReceiveMsg::new(
+            mock_packet(to_json_binary(&data).unwrap()),
+            Addr::unchecked(RELAYER_ADDR),
+        );
+        let res = Ics721Contract::default()
+            .ibc_packet_receive(deps, mock_env(), packet)
+            .unwrap();
+        match res.messages[0].msg.clone() {
+            cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => {
+                match from_json::<ExecuteMsg>(msg).unwrap() {
+                    ExecuteMsg::Callback(CallbackMsg::Conjunction { operands }) => operands,
+                    _ => panic!("unexpected execute msg"),
+                }
+            }
+            _ => panic!("unexpected cosmos msg"),
+        }
+    };
+    let is_add_claims = |msg: &WasmMsg, expected_receiver: &str| match msg {
+        WasmMsg::Execute { msg, .. } => matches!(
+            from_json::<ExecuteMsg>(msg).unwrap(),
+            ExecuteMsg::Callback(CallbackMsg::AddClaims { receiver, .. }) if receiver == expected_receiver
+        ),
+        _ => false,
+    };
+
+    // invalid receiver: NFT is held in custody and claims are added
+    let operands = receive(deps.as_mut(), "Invalid-Receiver", None);
+    assert_eq!(operands.len(), 3);
+    assert!(is_add_claims(&operands[1], "Invalid-Receiver"));
+    assert_eq!(INBOX_FALLBACK.may_load(&deps.storage).unwrap(), None);
+
+    // valid receiver: NFT is delivered, fallback is kept in case delivery fails
+    let operands = receive(deps.as_mut(), "blue", None);
+    assert_eq!(operands.len(), 2);
+    let fallback = INBOX_FALLBACK.load(&deps.storage).unwrap();
+    let fallback_operands = match fallback.clone() {
+        WasmMsg::Execute { msg, .. } => match from_json::<ExecuteMsg>(msg).unwrap() {
+            ExecuteMsg::Callback(CallbackMsg::Conjunction { operands }) => operands,
+            _ => panic!("unexpected execute msg"),
+        },
+        _ => panic!("unexpected wasm msg"),
+    };
+    assert_eq!(fallback_operands.len(), 3);
+    assert!(is_add_claims(&fallback_operands[1], "blue"));
+
+    // failed delivery dispatches fallback
+    let rep = Reply {
+        id: ACK_AND_DO_NOTHING_REPLY_ID,
+        result: SubMsgResult::Err("some failure".to_string()),
+    };
+    let res = Ics721Contract::default()
+        .reply(deps.as_mut(), mock_env(), rep)
+        .unwrap();
+    assert_eq!(res.data, None);
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_always(fallback, ACK_AND_DO_NOTHING_REPLY_ID)]
+    );
+    assert_eq!(INBOX_FALLBACK.may_load(&deps.storage).unwrap(), None);
+    // ack result is kept for fallback reply
+    assert!(INCOMING_ACK_RESULT
+        .may_load(&deps.storage)
+        .unwrap()
+        .is_some());
+
+    // failed fallback fails ack
+    let rep = Reply {
+        id: ACK_AND_DO_NOTHING_REPLY_ID,
+        result: SubMsgResult::Err("some failure".to_string()),
+    };
+    let res = Ics721Contract::default()
+        .reply(deps.as_mut(), mock_env(), rep)
+        .unwrap();
+    assert_eq!(res.data, Some(ack_fail("some failure".to_string())));
+
+    // no fallback for hooks, so failing hook rejects transfer
+    let memo = format!(
+        r#"{{"wasm":{{"contract":"marketplace","msg":"{}"}}}}"#,
+        to_json_binary("list").unwrap().to_base64()
+    );
+    let operands = receive(deps.as_mut(), "marketplace", Some(memo));
+    assert_eq!(operands.len(), 3);
+    assert!(!operands.iter().any(|op| is_add_claims(op, "marketplace")));
+    assert_eq!(INBOX_FALLBACK.may_load(&deps.storage).unwrap(), None);
+    let rep = Reply {
+        id: ACK_AND_DO_NOTHING_REPLY_ID,
+        result: SubMsgResult::Err("hook failure".to_string()),
+    };
+    let res = Ics721Contract::default()
+        .reply(deps.as_mut(), mock_env(), rep)
+        .unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(res.data, Some(ack_fail("hook failure".to_string())));
+}
+
+#[test]
+fn test_ibc_packet_receive_name_service() {
+    let mut deps = mock_dependencies();
+    CW721_CODE_ID
+        .save(&mut deps.storage, &CW721_BASE_CODE_ID)
+        .unwrap();
+    let dest_class_id = format!("{}/{}/{}", CONTRACT_PORT, CHANNEL_ID, "id");
+    CLASS_ID_AND_NFT_CONTRACT_INFO
+        .save(
+            &mut deps.storage,
+            &ClassId::new(dest_class_id.clone()),
+            &ClassIdInfo {
+                class_id: ClassId::new(dest_class_id),
+                address: Addr::unchecked("cosmos2contract"),
+            },
+        )
+        .unwrap();
+    PO.set_pauser(&mut deps.storage, &deps.api, None).unwrap();
+    NAME_SERVICE
+        .save(&mut deps.storage, &Addr::unchecked("names"))
+        .unwrap();
+    // only "alice.stars" is known to the name service
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart { contract_addr, msg } if contract_addr == "names" => {
+            match from_json::<NameServiceQueryMsg>(msg).unwrap() {
+                NameServiceQueryMsg::AssociatedAddress { name } if name == "alice.stars" => {
+                    QuerierResult::Ok(ContractResult::Ok(to_json_binary("alice").unwrap()))
+                }
+                NameServiceQueryMsg::AssociatedAddress { name } => {
+                    QuerierResult::Ok(ContractResult::Err(format!("name {name} not found")))
+                }
+            }
+        }
+        _ => QuerierResult::Err(SystemError::NoSuchContract {
+            addr: "unknown".to_string(),
+        }),
+    });
+
+    let receive = |deps: DepsMut, receiver: &str| {
+        let data = NonFungibleTokenPacketData {
+            class_id: ClassId::new("id"),
+            class_uri: None,
+            class_data: None,
+            token_ids: vec![TokenId::new("1")],
+            token_uris: None,
+            token_data: None,
+            sender: "violet".to_string(),
+            receiver: receiver.to_string(),
+            memo: None,
+        };
+        let packet = IbcPacketReceiveMsg::new(
+            mock_packet(to_json_binary(&data).unwrap()),
+            Addr::unchecked(RELAYER_ADDR),
+        );
+        Ics721Contract::default()
+            .ibc_packet_receive(deps, mock_env(), packet)
+            .unwrap()
+    };
+
+    // name is resolved
+    let res = receive(deps.as_mut(), "alice.stars");
+    let operands = match res.messages[0].msg.clone() {
+        cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => {
+            match from_json::<ExecuteMsg>(msg).unwrap() {
+                ExecuteMsg::Callback(CallbackMsg::Conjunction { operands }) => operands,
+                _ => panic!("unexpected execute msg"),
+            }
+        }
+        _ => panic!("unexpected cosmos msg"),
+    };
+    match operands[0].clone() {
+        WasmMsg::Execute { msg, .. } => match from_json::<ExecuteMsg>(msg).unwrap() {
+            ExecuteMsg::Callback(CallbackMsg::CreateVouchers { receiver, .. }) => {
+                assert_eq!(receiver, "alice")
+            }
+            _ => panic!("unexpected execute msg"),
+        },
+        _ => panic!("unexpected wasm msg"),
+    }
+
+    // unknown name fails
+    let res = receive(deps.as_mut(), "bob.stars");
+    let error = try_get_ack_error(&IbcAcknowledgement::new(res.acknowledgement));
+    assert_eq!(
+        error,
+        Some(
+            ContractError::NameNotResolved {
+                name: "bob.stars".to_string(),
+                name_service: "names".to_string(),
+            }
+            .to_string()
+        )
+    );
+}
+
 #[test]
 fn test_ibc_packet_receive_invalid_packet_data() {
     // the actual message used here is unimportant. this just
@@ -769,6 +2301,8 @@ fn test_different_memo_ignored() {
                     ack_callback_addr: None,
                     receive_callback_data: Some(dest_callback),
                     receive_callback_addr: None,
+                    timeout_callback_data: None,
+                    timeout_callback_addr: None,
                 }),
                 extra: None,
             })
@@ -2,10 +2,10 @@ use anyhow::Result;
 use bech32::{decode, encode, Hrp};
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    from_json, instantiate2_address, to_json_binary, Addr, Api, Binary, CanonicalAddr, Decimal,
-    Deps, DepsMut, Empty, Env, GovMsg, IbcTimeout, IbcTimeoutBlock, MemoryStorage, MessageInfo,
-    RecoverPubkeyError, Reply, Response, StdError, StdResult, Storage, Timestamp,
-    VerificationError, WasmMsg,
+    from_json, instantiate2_address, testing::MockApi, to_json_binary, Addr, Api, Binary,
+    CanonicalAddr, Decimal, Deps, DepsMut, Empty, Env, GovMsg, IbcTimeout, IbcTimeoutBlock,
+    MemoryStorage, MessageInfo, RecoverPubkeyError, Reply, Response, StdError, StdResult, Storage,
+    Timestamp, VerificationError, WasmMsg,
 };
 use cw2::set_contract_version;
 use cw721::{
@@ -18,18 +18,30 @@ use cw721_metadata_onchain::msg::{
 };
 use cw_cii::{Admin, ContractInstantiateInfo};
 use cw_multi_test::{
-    AddressGenerator, App, AppBuilder, BankKeeper, Contract, ContractWrapper, DistributionKeeper,
-    Executor, FailingModule, IbcAcceptingModule, Router, StakeKeeper, StargateFailing, WasmKeeper,
+    AddressGenerator, App, AppBuilder, AppResponse, BankKeeper, Contract, ContractWrapper,
+    DistributionKeeper, Executor, FailingModule, IbcAcceptingModule, Router, StakeKeeper,
+    StargateFailing, WasmKeeper,
 };
 use cw_pause_once::PauseError;
+use k256::ecdsa::{signature::hazmat::PrehashSigner, Signature, SigningKey};
+use ripemd::Ripemd160;
 use sha2::{digest::Update, Digest, Sha256};
 
 use crate::{
     execute::Ics721Execute,
     ibc::Ics721Ibc,
-    msg::{CallbackMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg},
+    msg::{
+        AdminCleanAction, AdminCleanAndUnescrowEntry, AdminCleanOutcome, CallbackMsg, ClaimProof,
+        Cw721CodeIdOverridesResponse, Cw721CodeIdTarget, ExecuteMsg, InstantiateMsg,
+        InvariantCheckResponse, InvariantViolation, InvariantViolationKind, MigrateMsg,
+        NameServiceQueryMsg, QueryMsg,
+    },
     query::Ics721Query,
-    state::{CollectionData, UniversalAllNftInfoResponse},
+    state::{
+        Claim, CollectionData, HistoryAction, HistoryConfig, HistoryOutcome, HistoryRecord,
+        InboxConfig, UniversalAllNftInfoResponse, UnknownMetadataPolicy, VoucherFlavour,
+        VoucherProfile,
+    },
     token_types::VoucherCreation,
     ContractError,
 };
@@ -212,11 +224,11 @@ impl Api for MockApiBech32 {
 
     fn secp256k1_verify(
         &self,
-        _message_hash: &[u8],
-        _signature: &[u8],
-        _public_key: &[u8],
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
     ) -> Result<bool, VerificationError> {
-        unimplemented!()
+        MockApi::default().secp256k1_verify(message_hash, signature, public_key)
     }
 
     fn secp256k1_recover_pubkey(
@@ -518,6 +530,8 @@ impl Test {
             .query_wasm_smart(
                 self.ics721.clone(),
                 &QueryMsg::OutgoingChannels {
+                    channel_id: None,
+                    class_id: None,
                     start_after: None,
                     limit: None,
                 },
@@ -531,6 +545,8 @@ impl Test {
             .query_wasm_smart(
                 self.ics721.clone(),
                 &QueryMsg::OutgoingChannels {
+                    channel_id: None,
+                    class_id: None,
                     start_after: None,
                     limit: None,
                 },
@@ -579,6 +595,20 @@ fn cw721_base_contract() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+fn cw721_migratable_contract() -> Box<dyn Contract<Empty>> {
+    fn migrate(_deps: DepsMut, _env: Env, _msg: Empty) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    let contract = ContractWrapper::new(
+        cw721_metadata_onchain::entry::execute,
+        cw721_metadata_onchain::entry::instantiate,
+        cw721_metadata_onchain::entry::query,
+    )
+    .with_migrate(migrate);
+    Box::new(contract)
+}
+
 fn cw721_v016_base_contract() -> Box<dyn Contract<Empty>> {
     use cw721_base_016 as v016;
     let contract = ContractWrapper::new(
@@ -610,6 +640,15 @@ fn incoming_proxy_contract() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+fn name_service_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        name_service_tester::execute,
+        name_service_tester::instantiate,
+        name_service_tester::query,
+    );
+    Box::new(contract)
+}
+
 fn outgoing_proxy_contract() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
         cw_ics721_outgoing_proxy_rate_limit::contract::execute,
@@ -1837,6 +1876,7 @@ fn test_no_proxy_unknown_msg() {
                 height: 10,
             }),
             memo: None,
+            refund_address: None,
         })
         .unwrap(),
     };
@@ -1875,6 +1915,7 @@ fn test_no_proxy_unauthorized() {
                 height: 10,
             }),
             memo: None,
+            refund_address: None,
         })
         .unwrap(),
     };
@@ -1972,6 +2013,7 @@ fn test_proxy_authorized() {
                             height: 10,
                         }),
                         memo: None,
+                        refund_address: None,
                     })
                     .unwrap(),
                 })
@@ -2008,6 +2050,7 @@ fn test_receive_nft() {
                             height: 10,
                         }),
                         memo: None,
+                        refund_address: None,
                     })
                     .unwrap(),
                 }),
@@ -2092,6 +2135,7 @@ fn test_receive_nft() {
                             height: 10,
                         }),
                         memo: None,
+                        refund_address: None,
                     })
                     .unwrap(),
                 }),
@@ -2174,6 +2218,7 @@ fn test_admin_clean_and_unescrow_nft() {
                             height: 10,
                         }),
                         memo: None,
+                        refund_address: None,
                     })
                     .unwrap(),
                 }),
@@ -2287,6 +2332,2175 @@ fn test_admin_clean_and_unescrow_nft() {
     }
 }
 
+#[test]
+fn test_admin_clean_and_unescrow_nfts() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    // escrow 2 nfts by ics721
+    let channel = "channel-0".to_string();
+    let mut token_ids = vec![];
+    for _ in 0..2 {
+        let token_id = test.execute_cw721_mint(test.ics721.clone()).unwrap();
+        test.app
+            .execute_contract(
+                test.source_cw721.clone(),
+                test.ics721.clone(),
+                &ExecuteMsg::ReceiveNft(cw721::receiver::Cw721ReceiveMsg {
+                    sender: test.source_cw721_owner.to_string(),
+                    token_id: token_id.clone(),
+                    msg: to_json_binary(&IbcOutgoingMsg {
+                        receiver: NFT_OWNER_TARGET_CHAIN.to_string(),
+                        channel_id: channel.clone(),
+                        timeout: IbcTimeout::with_block(IbcTimeoutBlock {
+                            revision: 0,
+                            height: 10,
+                        }),
+                        memo: None,
+                        refund_address: None,
+                    })
+                    .unwrap(),
+                }),
+                &[],
+            )
+            .unwrap();
+        token_ids.push(token_id);
+    }
+    assert_eq!(test.query_outgoing_channels().len(), 2);
+
+    let class_id = ClassId::new(test.source_cw721.to_string());
+    let recipient = test.app.api().addr_make("recipient");
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    let entries: Vec<AdminCleanAndUnescrowEntry> = token_ids
+        .iter()
+        .map(|token_id| AdminCleanAndUnescrowEntry {
+            recipient: recipient.to_string(),
+            token_id: token_id.clone(),
+            class_id: class_id.to_string(),
+            collection: test.source_cw721.to_string(),
+        })
+        .collect();
+    let unknown_entry = AdminCleanAndUnescrowEntry {
+        recipient: recipient.to_string(),
+        token_id: token_ids[0].clone(),
+        class_id: "unknown".to_string(),
+        collection: test.source_cw721.to_string(),
+    };
+
+    // dry run lists outcome for each entry
+    let outcomes: Vec<AdminCleanOutcome> = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::AdminCleanDryRun {
+                burn: vec![],
+                unescrow: [entries.clone(), vec![unknown_entry.clone()]].concat(),
+            },
+        )
+        .unwrap();
+    assert_eq!(outcomes.len(), 3);
+    assert_eq!(
+        outcomes[0].action,
+        AdminCleanAction::Transfer {
+            recipient: recipient.to_string()
+        }
+    );
+    assert_eq!(outcomes[1].action, outcomes[0].action);
+    assert_eq!(
+        outcomes[2].action,
+        AdminCleanAction::Failed {
+            error: ContractError::NoNftContractForClassId("unknown".to_string()).to_string()
+        }
+    );
+    // dry run doesn't change state
+    assert_eq!(test.query_outgoing_channels().len(), 2);
+
+    // non admin can't call
+    let non_admin = test.app.api().addr_make("not_admin");
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            non_admin,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminCleanAndUnescrowNfts {
+                entries: entries.clone(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // a failing entry reverts the whole batch
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            admin.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::AdminCleanAndUnescrowNfts {
+                entries: [entries.clone(), vec![unknown_entry]].concat(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::NoNftContractForClassId("unknown".to_string())
+    );
+    assert_eq!(test.query_outgoing_channels().len(), 2);
+
+    let res = test
+        .app
+        .execute_contract(
+            admin,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminCleanAndUnescrowNfts { entries },
+            &[],
+        )
+        .unwrap();
+    // an event is emitted for each token
+    let events: Vec<_> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm-ics721_admin_clean")
+        .collect();
+    assert_eq!(events.len(), 2);
+    assert!(events[0]
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "action" && attr.value == "transfer"));
+    // outgoing channel entries are removed and nfts are unescrowed
+    assert_eq!(test.query_outgoing_channels().len(), 0);
+    for token_id in token_ids {
+        let UniversalAllNftInfoResponse { access, .. } = test.query_cw721_all_nft_info(token_id);
+        assert_eq!(access.owner, recipient.to_string());
+    }
+}
+
+#[test]
+fn test_check_invariants() {
+    let mut test = Test::new(false, false, None, None, cw721_base_contract(), true);
+    let channel = "channel-0".to_string();
+    let owner = test.app.api().addr_make("owner");
+    // escrowed by ics721, and one not escrowed (simplify: receive nft without transfer)
+    let token_id_escrowed = test.execute_cw721_mint(test.ics721.clone()).unwrap();
+    let token_id_not_escrowed = test.execute_cw721_mint(owner.clone()).unwrap();
+    for token_id in [token_id_escrowed.clone(), token_id_not_escrowed.clone()] {
+        test.app
+            .execute_contract(
+                test.source_cw721.clone(),
+                test.ics721.clone(),
+                &ExecuteMsg::ReceiveNft(cw721::receiver::Cw721ReceiveMsg {
+                    sender: test.source_cw721_owner.to_string(),
+                    token_id,
+                    msg: to_json_binary(&IbcOutgoingMsg {
+                        receiver: NFT_OWNER_TARGET_CHAIN.to_string(),
+                        channel_id: channel.clone(),
+                        timeout: IbcTimeout::with_block(IbcTimeoutBlock {
+                            revision: 0,
+                            height: 10,
+                        }),
+                        memo: None,
+                        refund_address: None,
+                    })
+                    .unwrap(),
+                }),
+                &[],
+            )
+            .unwrap();
+    }
+    let res: InvariantCheckResponse = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::CheckEscrowInvariants {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res,
+        InvariantCheckResponse {
+            checked: 2,
+            last: None,
+            violations: vec![InvariantViolation {
+                class_id: ClassId::new(test.source_cw721.to_string()),
+                token_id: TokenId::new(token_id_not_escrowed.clone()),
+                channel_id: channel.clone(),
+                kind: InvariantViolationKind::WrongOwner {
+                    nft_contract: test.source_cw721.clone(),
+                    owner: owner.to_string(),
+                },
+            }],
+        }
+    );
+    // paginated
+    let res: InvariantCheckResponse = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::CheckEscrowInvariants {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.checked, 1);
+    let res: InvariantCheckResponse = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::CheckEscrowInvariants {
+                start_after: res.last,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.checked, 1);
+    assert!(res.last.is_some());
+    let res: InvariantCheckResponse = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::CheckEscrowInvariants {
+                start_after: res.last,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.checked, 0);
+    assert_eq!(res.last, None);
+
+    // vouchers: one minted, one missing, and one for unknown class id
+    let class_id = ClassId::new(format!(
+        "wasm.{}/{}/{}",
+        test.ics721,
+        CHANNEL_TARGET_CHAIN,
+        test.app.api().addr_make(COLLECTION_CONTRACT_SOURCE_CHAIN)
+    ));
+    test.app
+        .execute_contract(
+            test.ics721.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Callback(CallbackMsg::CreateVouchers {
+                receiver: owner.to_string(),
+                create: VoucherCreation {
+                    class: Class {
+                        id: class_id.clone(),
+                        uri: None,
+                        data: None,
+                    },
+                    tokens: vec![Token {
+                        id: TokenId::new("1"),
+                        uri: None,
+                        data: None,
+                    }],
+                },
+            }),
+            &[],
+        )
+        .unwrap();
+    test.app
+        .execute_contract(
+            test.ics721.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Callback(CallbackMsg::AddIncomingChannelEntries(vec![
+                ((class_id.clone(), TokenId::new("1")), channel.clone()),
+                ((class_id.clone(), TokenId::new("2")), channel.clone()),
+                (
+                    (ClassId::new("unknown"), TokenId::new("1")),
+                    channel.clone(),
+                ),
+            ])),
+            &[],
+        )
+        .unwrap();
+    let voucher_contract: Addr = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::NftContract {
+                class_id: class_id.to_string(),
+            },
+        )
+        .unwrap();
+    let res: InvariantCheckResponse = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::CheckVoucherInvariants {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.checked, 3);
+    assert_eq!(res.violations.len(), 2);
+    assert!(res.violations.contains(&InvariantViolation {
+        class_id: class_id.clone(),
+        token_id: TokenId::new("2"),
+        channel_id: channel.clone(),
+        kind: InvariantViolationKind::Missing {
+            nft_contract: voucher_contract,
+        },
+    }));
+    assert!(res.violations.contains(&InvariantViolation {
+        class_id: ClassId::new("unknown"),
+        token_id: TokenId::new("1"),
+        channel_id: channel,
+        kind: InvariantViolationKind::Orphaned {},
+    }));
+}
+
+#[test]
+fn test_history() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    let channel = "channel-0".to_string();
+    let send_nft = |test: &mut Test, token_id: String| {
+        test.app
+            .execute_contract(
+                test.source_cw721.clone(),
+                test.ics721.clone(),
+                &ExecuteMsg::ReceiveNft(cw721::receiver::Cw721ReceiveMsg {
+                    sender: test.source_cw721_owner.to_string(),
+                    token_id,
+                    msg: to_json_binary(&IbcOutgoingMsg {
+                        receiver: NFT_OWNER_TARGET_CHAIN.to_string(),
+                        channel_id: "channel-0".to_string(),
+                        timeout: IbcTimeout::with_block(IbcTimeoutBlock {
+                            revision: 0,
+                            height: 10,
+                        }),
+                        memo: None,
+                        refund_address: None,
+                    })
+                    .unwrap(),
+                }),
+                &[],
+            )
+            .unwrap();
+    };
+    let query_history = |test: &Test, address: Option<String>, token_id: Option<String>| {
+        let class_id = token_id.as_ref().map(|_| test.source_cw721.to_string());
+        test.app
+            .wrap()
+            .query_wasm_smart::<Vec<(u64, HistoryRecord)>>(
+                test.ics721.clone(),
+                &QueryMsg::History {
+                    address,
+                    class_id,
+                    token_id,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap()
+    };
+
+    // disabled by default, so nothing is recorded
+    let token_id = test.execute_cw721_mint(test.ics721.clone()).unwrap();
+    send_nft(&mut test, token_id);
+    assert!(query_history(&test, None, None).is_empty());
+
+    // non admin can't enable history
+    let config = HistoryConfig { retention: 100 };
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            test.app.api().addr_make("not_admin"),
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetHistory {
+                config: Some(config.clone()),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+    test.app
+        .execute_contract(
+            admin.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetHistory {
+                config: Some(config.clone()),
+            },
+            &[],
+        )
+        .unwrap();
+    let history_config: Option<HistoryConfig> = test
+        .app
+        .wrap()
+        .query_wasm_smart(test.ics721.clone(), &QueryMsg::HistoryConfig {})
+        .unwrap();
+    assert_eq!(history_config, Some(config));
+
+    // send is recorded, once packet is dispatched
+    let token_id = test.execute_cw721_mint(test.ics721.clone()).unwrap();
+    send_nft(&mut test, token_id.clone());
+    let height = test.app.block_info().height;
+    let send_record = HistoryRecord {
+        action: HistoryAction::Send,
+        class_id: ClassId::new(test.source_cw721.to_string()),
+        token_id: TokenId::new(token_id.clone()),
+        channel_id: Some(channel),
+        // mock chain doesn't provide sequence
+        sequence: None,
+        address: test.source_cw721_owner.to_string(),
+        counterparty: Some(NFT_OWNER_TARGET_CHAIN.to_string()),
+        height,
+        outcome: HistoryOutcome::Pending,
+    };
+    assert_eq!(
+        query_history(&test, Some(test.source_cw721_owner.to_string()), None),
+        vec![(0, send_record.clone())]
+    );
+
+    // admin actions are recorded
+    let recipient = test.app.api().addr_make("recipient");
+    test.app
+        .execute_contract(
+            admin,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminCleanAndUnescrowNft {
+                recipient: recipient.to_string(),
+                token_id: token_id.clone(),
+                class_id: test.source_cw721.to_string(),
+                collection: test.source_cw721.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    let admin_record = HistoryRecord {
+        action: HistoryAction::AdminTransfer,
+        channel_id: None,
+        sequence: None,
+        address: recipient.to_string(),
+        counterparty: None,
+        outcome: HistoryOutcome::Success,
+        ..send_record.clone()
+    };
+    assert_eq!(
+        query_history(&test, None, Some(token_id)),
+        vec![(0, send_record), (1, admin_record.clone())]
+    );
+    assert_eq!(
+        query_history(&test, Some(recipient.to_string()), None),
+        vec![(1, admin_record)]
+    );
+
+    // records are kept during retention
+    let prune = |test: &mut Test| {
+        test.app
+            .execute_contract(
+                test.app.api().addr_make("anyone"),
+                test.ics721.clone(),
+                &ExecuteMsg::PruneHistory { limit: None },
+                &[],
+            )
+            .unwrap();
+    };
+    prune(&mut test);
+    assert_eq!(query_history(&test, None, None).len(), 2);
+    test.app.update_block(|block| block.height += 101);
+    prune(&mut test);
+    assert!(query_history(&test, None, None).is_empty());
+}
+
+#[test]
+fn test_legacy_voucher_flavour() {
+    // cw721 v0.16 is the only cw721 code available for vouchers
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_v016_base_contract(),
+        false,
+    );
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    let class_id = ClassId::new(format!(
+        "wasm.{}/{}/{}",
+        test.ics721,
+        CHANNEL_TARGET_CHAIN,
+        test.app.api().addr_make(COLLECTION_CONTRACT_SOURCE_CHAIN)
+    ));
+    let create_vouchers = |test: &mut Test| {
+        test.app.execute_contract(
+            test.ics721.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Callback(CallbackMsg::CreateVouchers {
+                receiver: test.source_cw721_owner.to_string(),
+                create: VoucherCreation {
+                    class: Class {
+                        id: class_id.clone(),
+                        uri: None,
+                        data: None,
+                    },
+                    tokens: vec![Token {
+                        id: TokenId::new("1"),
+                        uri: Some("https://ark.pass/1".to_string()),
+                        data: None,
+                    }],
+                },
+            }),
+            &[],
+        )
+    };
+    let query_flavour = |test: &Test, code_id: Option<u64>| -> VoucherFlavour {
+        test.app
+            .wrap()
+            .query_wasm_smart(test.ics721.clone(), &QueryMsg::VoucherFlavour { code_id })
+            .unwrap()
+    };
+    assert_eq!(query_flavour(&test, None), VoucherFlavour::V019);
+
+    // v0.19 instantiate msg is rejected by cw721 v0.16
+    create_vouchers(&mut test).unwrap_err();
+
+    // only admin can set flavour
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            test.source_cw721_owner.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetVoucherFlavour {
+                code_id: test.source_cw721_id,
+                flavour: Some(VoucherFlavour::V016),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    test.app
+        .execute_contract(
+            admin.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetVoucherFlavour {
+                code_id: test.source_cw721_id,
+                flavour: Some(VoucherFlavour::V016),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(query_flavour(&test, None), VoucherFlavour::V016);
+    assert_eq!(
+        query_flavour(&test, Some(test.source_cw721_id)),
+        VoucherFlavour::V016
+    );
+    assert_eq!(query_flavour(&test, Some(42)), VoucherFlavour::V019);
+
+    create_vouchers(&mut test).unwrap();
+    let nft_contract: Addr = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::NftContract {
+                class_id: class_id.to_string(),
+            },
+        )
+        .unwrap();
+    let contract_info: cw721_016::ContractInfoResponse = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            nft_contract.clone(),
+            &cw721_base_016::QueryMsg::<Empty>::ContractInfo {},
+        )
+        .unwrap();
+    assert_eq!(contract_info.name, class_id.to_string());
+    let nft_info: cw721_016::NftInfoResponse<Option<Empty>> = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            nft_contract.clone(),
+            &cw721_base_016::QueryMsg::<Empty>::NftInfo {
+                token_id: "1".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(nft_info.token_uri, Some("https://ark.pass/1".to_string()));
+    let owner: cw721_016::OwnerOfResponse = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            nft_contract,
+            &cw721_base_016::QueryMsg::<Empty>::OwnerOf {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(owner.owner, test.source_cw721_owner.to_string());
+
+    // unset flavour
+    test.app
+        .execute_contract(
+            admin,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetVoucherFlavour {
+                code_id: test.source_cw721_id,
+                flavour: None,
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(query_flavour(&test, None), VoucherFlavour::V019);
+}
+
+#[test]
+fn test_cw721_code_id_override() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    let override_code_id = test.app.store_code(cw721_base_contract());
+    let class_id = ClassId::new(format!(
+        "wasm.{}/{}/{}",
+        test.ics721,
+        CHANNEL_TARGET_CHAIN,
+        test.app.api().addr_make(COLLECTION_CONTRACT_SOURCE_CHAIN)
+    ));
+    let set_override = |test: &mut Test, target: Cw721CodeIdTarget, code_id: Option<u64>| {
+        test.app.execute_contract(
+            admin.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetCw721CodeIdOverride { target, code_id },
+            &[],
+        )
+    };
+
+    // code ID must exist
+    set_override(
+        &mut test,
+        Cw721CodeIdTarget::Channel {
+            channel_id: CHANNEL_TARGET_CHAIN.to_string(),
+        },
+        Some(42),
+    )
+    .unwrap_err();
+    // prefix must not be empty
+    let err: ContractError = set_override(
+        &mut test,
+        Cw721CodeIdTarget::ClassIdPrefix {
+            prefix: "".to_string(),
+        },
+        Some(override_code_id),
+    )
+    .unwrap_err()
+    .downcast()
+    .unwrap();
+    assert_eq!(err, ContractError::EmptyClassIdPrefix {});
+
+    set_override(
+        &mut test,
+        Cw721CodeIdTarget::Channel {
+            channel_id: CHANNEL_TARGET_CHAIN.to_string(),
+        },
+        Some(override_code_id),
+    )
+    .unwrap();
+    let overrides: Cw721CodeIdOverridesResponse = test
+        .app
+        .wrap()
+        .query_wasm_smart(test.ics721.clone(), &QueryMsg::Cw721CodeIdOverrides {})
+        .unwrap();
+    assert_eq!(
+        overrides,
+        Cw721CodeIdOverridesResponse {
+            channels: vec![(CHANNEL_TARGET_CHAIN.to_string(), override_code_id)],
+            class_id_prefixes: vec![],
+        }
+    );
+
+    // predicted address matches the voucher contract
+    let predicted: Addr = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::GetInstantiate2NftContract {
+                class_id: class_id.to_string(),
+                cw721_code_id: None,
+            },
+        )
+        .unwrap();
+    test.app
+        .execute_contract(
+            test.ics721.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Callback(CallbackMsg::CreateVouchers {
+                receiver: test.source_cw721_owner.to_string(),
+                create: VoucherCreation {
+                    class: Class {
+                        id: class_id.clone(),
+                        uri: None,
+                        data: None,
+                    },
+                    tokens: vec![Token {
+                        id: TokenId::new("1"),
+                        uri: None,
+                        data: None,
+                    }],
+                },
+            }),
+            &[],
+        )
+        .unwrap();
+    let nft_contract: Addr = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::NftContract {
+                class_id: class_id.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(nft_contract, predicted);
+    let contract_info = test
+        .app
+        .wrap()
+        .query_wasm_contract_info(nft_contract)
+        .unwrap();
+    assert_eq!(contract_info.code_id, override_code_id);
+
+    // remove override
+    set_override(
+        &mut test,
+        Cw721CodeIdTarget::Channel {
+            channel_id: CHANNEL_TARGET_CHAIN.to_string(),
+        },
+        None,
+    )
+    .unwrap();
+    let overrides: Cw721CodeIdOverridesResponse = test
+        .app
+        .wrap()
+        .query_wasm_smart(test.ics721.clone(), &QueryMsg::Cw721CodeIdOverrides {})
+        .unwrap();
+    assert!(overrides.channels.is_empty());
+}
+
+#[test]
+fn test_migrate_voucher_contracts() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_migratable_contract(),
+        true,
+    );
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    let new_code_id = test.app.store_code(cw721_migratable_contract());
+    let class_ids: Vec<ClassId> = ["a", "b", "c"]
+        .iter()
+        .map(|collection| {
+            ClassId::new(format!(
+                "wasm.{}/{}/{}",
+                test.ics721,
+                CHANNEL_TARGET_CHAIN,
+                test.app.api().addr_make(collection)
+            ))
+        })
+        .collect();
+    let mut nft_contracts = vec![];
+    for class_id in &class_ids {
+        test.app
+            .execute_contract(
+                test.ics721.clone(),
+                test.ics721.clone(),
+                &ExecuteMsg::Callback(CallbackMsg::CreateVouchers {
+                    receiver: test.source_cw721_owner.to_string(),
+                    create: VoucherCreation {
+                        class: Class {
+                            id: class_id.clone(),
+                            uri: None,
+                            data: None,
+                        },
+                        tokens: vec![Token {
+                            id: TokenId::new("1"),
+                            uri: None,
+                            data: None,
+                        }],
+                    },
+                }),
+                &[],
+            )
+            .unwrap();
+        let nft_contract: Addr = test
+            .app
+            .wrap()
+            .query_wasm_smart(
+                test.ics721.clone(),
+                &QueryMsg::NftContract {
+                    class_id: class_id.to_string(),
+                },
+            )
+            .unwrap();
+        nft_contracts.push(nft_contract);
+    }
+    // cw721 admin hands over the first two contracts to ics721
+    for nft_contract in &nft_contracts[..2] {
+        test.app
+            .execute(
+                admin.clone(),
+                WasmMsg::UpdateAdmin {
+                    contract_addr: nft_contract.to_string(),
+                    admin: test.ics721.to_string(),
+                }
+                .into(),
+            )
+            .unwrap();
+    }
+    let migrate = |test: &mut Test, sender: Addr, start_after: Option<String>| {
+        test.app.execute_contract(
+            sender,
+            test.ics721.clone(),
+            &ExecuteMsg::MigrateVoucherContracts {
+                new_code_id,
+                msg: to_json_binary(&Empty {}).unwrap(),
+                start_after,
+                limit: Some(2),
+            },
+            &[],
+        )
+    };
+    let code_id = |test: &Test, nft_contract: &Addr| {
+        test.app
+            .wrap()
+            .query_wasm_contract_info(nft_contract)
+            .unwrap()
+            .code_id
+    };
+    let attribute = |res: &AppResponse, key: &str| {
+        res.events
+            .iter()
+            .filter(|e| e.ty == "wasm")
+            .flat_map(|e| e.attributes.iter())
+            .find(|a| a.key == key)
+            .map(|a| a.value.clone())
+            .unwrap()
+    };
+
+    // only admin can migrate
+    let not_admin = test.source_cw721_owner.clone();
+    let err: ContractError = migrate(&mut test, not_admin, None)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // class IDs are ordered, so contracts are migrated in order of class IDs
+    let mut sorted = class_ids
+        .iter()
+        .map(|class_id| class_id.to_string())
+        .zip(nft_contracts.clone())
+        .collect::<Vec<_>>();
+    sorted.sort();
+    let res = migrate(&mut test, admin.clone(), None).unwrap();
+    assert_eq!(attribute(&res, "last_class_id"), sorted[1].0);
+    let res = migrate(&mut test, admin.clone(), Some(sorted[1].0.clone())).unwrap();
+    assert_eq!(attribute(&res, "last_class_id"), "none");
+    assert_eq!(
+        res.events
+            .iter()
+            .filter(|e| e.ty == "wasm-ics721_migrate_voucher_contract")
+            .count(),
+        1
+    );
+
+    // contracts with ics721 as admin are migrated, others are skipped
+    assert_eq!(code_id(&test, &nft_contracts[0]), new_code_id);
+    assert_eq!(code_id(&test, &nft_contracts[1]), new_code_id);
+    assert_eq!(code_id(&test, &nft_contracts[2]), test.source_cw721_id);
+}
+
+#[test]
+fn test_register_voucher_contract() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    let class_id = ClassId::new(format!(
+        "wasm.{}/{}/{}",
+        test.ics721,
+        CHANNEL_TARGET_CHAIN,
+        test.app.api().addr_make(COLLECTION_CONTRACT_SOURCE_CHAIN)
+    ));
+    // mirror collection, deployed before using ics721
+    let mirror = test
+        .app
+        .instantiate_contract(
+            test.source_cw721_id,
+            test.source_cw721_owner.clone(),
+            &Cw721InstantiateMsg {
+                name: "mirror".to_string(),
+                symbol: "mirror".to_string(),
+                collection_info_extension: None,
+                creator: None,
+                minter: Some(test.ics721.to_string()),
+                withdraw_address: None,
+            },
+            &[],
+            "mirror",
+            None,
+        )
+        .unwrap();
+    let register = |test: &mut Test, sender: Addr, class_id: &ClassId, contract: &Addr| {
+        test.app
+            .execute_contract(
+                sender,
+                test.ics721.clone(),
+                &ExecuteMsg::RegisterVoucherContract {
+                    class_id: class_id.to_string(),
+                    contract: contract.to_string(),
+                },
+                &[],
+            )
+            .map_err(|err| err.downcast::<ContractError>().unwrap())
+    };
+
+    // only admin can register
+    let not_admin = test.source_cw721_owner.clone();
+    let err = register(&mut test, not_admin, &class_id, &mirror).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // ics721 must be minter
+    let source_cw721 = test.source_cw721.clone();
+    let err = register(&mut test, admin.clone(), &class_id, &source_cw721).unwrap_err();
+    assert_eq!(err, ContractError::NotMinter(source_cw721.to_string()));
+
+    register(&mut test, admin.clone(), &class_id, &mirror).unwrap();
+    let nft_contract: Addr = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::NftContract {
+                class_id: class_id.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(nft_contract, mirror);
+
+    // class ID and contract can't be registered twice
+    let err = register(&mut test, admin.clone(), &class_id, &mirror).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ClassIdAlreadyRegistered(class_id.to_string())
+    );
+    let err = register(&mut test, admin, &ClassId::new("other"), &mirror).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NftContractAlreadyRegistered(mirror.to_string())
+    );
+
+    // vouchers are minted into the mirror collection
+    test.app
+        .execute_contract(
+            test.ics721.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Callback(CallbackMsg::CreateVouchers {
+                receiver: test.source_cw721_owner.to_string(),
+                create: VoucherCreation {
+                    class: Class {
+                        id: class_id.clone(),
+                        uri: None,
+                        data: None,
+                    },
+                    tokens: vec![Token {
+                        id: TokenId::new("1"),
+                        uri: None,
+                        data: None,
+                    }],
+                },
+            }),
+            &[],
+        )
+        .unwrap();
+    let owner: cw721::msg::OwnerOfResponse = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            mirror,
+            &Cw721QueryMsg::OwnerOf {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(owner.owner, test.source_cw721_owner.to_string());
+}
+
+#[test]
+fn test_voucher_profile() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    // collection owner on home chain, holding the same bech32 data as the local creator
+    let creator = test.app.api().addr_make("creator");
+    let (_, creator_data) = decode(creator.as_str()).unwrap();
+    let home_owner = encode::<bech32::Bech32>(Hrp::parse("juno").unwrap(), &creator_data).unwrap();
+    let class_id = ClassId::new(format!(
+        "wasm.{}/{}/{}",
+        test.ics721,
+        CHANNEL_TARGET_CHAIN,
+        test.app.api().addr_make(COLLECTION_CONTRACT_SOURCE_CHAIN)
+    ));
+    let profile = VoucherProfile {
+        name: Some("Ark Pass".to_string()),
+        symbol: Some("ARK".to_string()),
+        description: Some("interchain pass".to_string()),
+        image: Some("https://ark.pass/image.png".to_string()),
+        external_link: None,
+    };
+    let set_profile = |test: &mut Test, sender: Addr, profile: Option<VoucherProfile>| {
+        test.app.execute_contract(
+            sender,
+            test.ics721.clone(),
+            &ExecuteMsg::SetVoucherProfile {
+                class_id: class_id.to_string(),
+                profile,
+            },
+            &[],
+        )
+    };
+
+    // home chain owner is unknown, since class hasn't been received yet
+    let err: ContractError = set_profile(&mut test, creator.clone(), Some(profile.clone()))
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+    set_profile(&mut test, admin.clone(), Some(profile.clone())).unwrap();
+    let stored: Option<VoucherProfile> = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::VoucherProfile {
+                class_id: class_id.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(stored, Some(profile.clone()));
+
+    // profile is applied on instantiation
+    test.app
+        .execute_contract(
+            test.ics721.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Callback(CallbackMsg::CreateVouchers {
+                receiver: test.source_cw721_owner.to_string(),
+                create: VoucherCreation {
+                    class: Class {
+                        id: class_id.clone(),
+                        uri: None,
+                        data: Some(
+                            to_json_binary(&CollectionData {
+                                owner: Some(home_owner),
+                                contract_info: Default::default(),
+                                name: "name".to_string(),
+                                symbol: "symbol".to_string(),
+                                extension: None,
+                                num_tokens: Some(1),
+                            })
+                            .unwrap(),
+                        ),
+                    },
+                    tokens: vec![Token {
+                        id: TokenId::new("1"),
+                        uri: None,
+                        data: None,
+                    }],
+                },
+            }),
+            &[],
+        )
+        .unwrap();
+    let nft_contract: Addr = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::NftContract {
+                class_id: class_id.to_string(),
+            },
+        )
+        .unwrap();
+    let collection_info: CollectionInfoAndExtensionResponse<DefaultOptionalCollectionExtension> =
+        test.app
+            .wrap()
+            .query_wasm_smart(
+                nft_contract,
+                &Cw721QueryMsg::GetCollectionInfoAndExtension {},
+            )
+            .unwrap();
+    assert_eq!(collection_info.name, "Ark Pass");
+    assert_eq!(collection_info.symbol, "ARK");
+    let extension = collection_info.extension.unwrap();
+    assert_eq!(extension.description, "interchain pass");
+    assert_eq!(extension.image, "https://ark.pass/image.png");
+
+    // home chain owner may update profile, it isn't pushed since ics721 isn't creator of the collection
+    let res = set_profile(&mut test, creator, None).unwrap();
+    let removed = res
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .find(|a| a.key == "profile")
+        .map(|a| a.value.clone());
+    assert_eq!(removed, Some("none".to_string()));
+    let res = set_profile(&mut test, admin, Some(profile)).unwrap();
+    let pushed = res
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .find(|a| a.key == "pushed")
+        .map(|a| a.value.clone());
+    assert_eq!(pushed, Some("false".to_string()));
+
+    // others can't
+    let not_owner = test.app.api().addr_make("not_owner");
+    let err: ContractError = set_profile(&mut test, not_owner, None)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn test_royalty_recipient() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    // ics721 is creator of voucher collections, so it may update collection info
+    test.app
+        .execute(
+            admin,
+            WasmMsg::Migrate {
+                contract_addr: test.ics721.to_string(),
+                new_code_id: test.ics721_id,
+                msg: to_json_binary(&MigrateMsg::WithUpdate {
+                    pauser: None,
+                    incoming_proxy: None,
+                    outgoing_proxy: None,
+                    cw721_base_code_id: None,
+                    cw721_admin: Some(test.ics721.to_string()),
+                    contract_addr_length: None,
+                })
+                .unwrap(),
+            }
+            .into(),
+        )
+        .unwrap();
+    // collection owner on home chain, holding the same bech32 data as the local creator
+    let owner_key = SigningKey::from_slice(&[1u8; 32]).unwrap();
+    let owner_pub_key = Binary::from(owner_key.verifying_key().to_encoded_point(true).as_bytes());
+    let creator_data = Ripemd160::digest(Sha256::digest(owner_pub_key.as_slice())).to_vec();
+    let creator = test
+        .app
+        .api()
+        .addr_humanize(&CanonicalAddr::from(creator_data.clone()))
+        .unwrap();
+    let home_owner = encode::<bech32::Bech32>(Hrp::parse("juno").unwrap(), &creator_data).unwrap();
+    let class_id = ClassId::new(format!(
+        "wasm.{}/{}/{}",
+        test.ics721,
+        CHANNEL_TARGET_CHAIN,
+        test.app.api().addr_make(COLLECTION_CONTRACT_SOURCE_CHAIN)
+    ));
+    test.app
+        .execute_contract(
+            test.ics721.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Callback(CallbackMsg::CreateVouchers {
+                receiver: test.source_cw721_owner.to_string(),
+                create: VoucherCreation {
+                    class: Class {
+                        id: class_id.clone(),
+                        uri: None,
+                        data: Some(
+                            to_json_binary(&CollectionData {
+                                owner: Some(home_owner.clone()),
+                                contract_info: Default::default(),
+                                name: "name".to_string(),
+                                symbol: "symbol".to_string(),
+                                extension: Some(CollectionExtension {
+                                    description: "description".to_string(),
+                                    explicit_content: None,
+                                    external_link: None,
+                                    image: "https://ark.pass/image.png".to_string(),
+                                    royalty_info: Some(RoyaltyInfo {
+                                        payment_address: Addr::unchecked(&home_owner),
+                                        share: Decimal::bps(1000),
+                                    }),
+                                    start_trading_time: None,
+                                }),
+                                num_tokens: Some(1),
+                            })
+                            .unwrap(),
+                        ),
+                    },
+                    tokens: vec![Token {
+                        id: TokenId::new("1"),
+                        uri: None,
+                        data: None,
+                    }],
+                },
+            }),
+            &[],
+        )
+        .unwrap();
+    let nft_contract: Addr = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::NftContract {
+                class_id: class_id.to_string(),
+            },
+        )
+        .unwrap();
+    let query_royalty_info = |test: &Test| {
+        let collection_info: CollectionInfoAndExtensionResponse<
+            DefaultOptionalCollectionExtension,
+        > = test
+            .app
+            .wrap()
+            .query_wasm_smart(
+                nft_contract.clone(),
+                &Cw721QueryMsg::GetCollectionInfoAndExtension {},
+            )
+            .unwrap();
+        collection_info.extension.unwrap().royalty_info.unwrap()
+    };
+    // home chain payment address can't be used
+    assert_eq!(query_royalty_info(&test).payment_address, test.ics721);
+
+    // only collection owner on home chain can set royalty recipient
+    let recipient = test.app.api().addr_make("recipient");
+    let not_owner = test.app.api().addr_make("not_owner");
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            not_owner.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::SetRoyaltyRecipient {
+                class_id: class_id.to_string(),
+                payment_address: recipient.to_string(),
+                proof: ClaimProof::Bech32 {},
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidOwnerProof(home_owner.clone()));
+
+    // signature must be signed by collection owner
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            creator.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::SetRoyaltyRecipient {
+                class_id: class_id.to_string(),
+                payment_address: recipient.to_string(),
+                proof: ClaimProof::Signature {
+                    pub_key: Binary::from([2u8; 33]),
+                    signature: Binary::from([1u8; 64]),
+                },
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidOwnerProof(home_owner.clone()));
+
+    // class without collection owner
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            creator.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::SetRoyaltyRecipient {
+                class_id: "unknown".to_string(),
+                payment_address: recipient.to_string(),
+                proof: ClaimProof::Bech32 {},
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoCollectionOwner("unknown".to_string()));
+
+    let res = test
+        .app
+        .execute_contract(
+            creator.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::SetRoyaltyRecipient {
+                class_id: class_id.to_string(),
+                payment_address: recipient.to_string(),
+                proof: ClaimProof::Bech32 {},
+            },
+            &[],
+        )
+        .unwrap();
+    let pushed = res
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .find(|a| a.key == "pushed")
+        .map(|a| a.value.clone());
+    assert_eq!(pushed, Some("true".to_string()));
+    let royalty_recipient: Option<Addr> = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::RoyaltyRecipient {
+                class_id: class_id.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(royalty_recipient, Some(recipient.clone()));
+    let royalty_info = query_royalty_info(&test);
+    assert_eq!(royalty_info.payment_address, recipient);
+    assert_eq!(royalty_info.share, Decimal::bps(1000));
+
+    // signed proof includes nonce, which is incremented on each update
+    let query_nonce = |test: &Test| -> u64 {
+        test.app
+            .wrap()
+            .query_wasm_smart(
+                test.ics721.clone(),
+                &QueryMsg::RoyaltyRecipientNonce {
+                    class_id: class_id.to_string(),
+                },
+            )
+            .unwrap()
+    };
+    assert_eq!(query_nonce(&test), 1);
+    let other_recipient = test.app.api().addr_make("other_recipient");
+    let msg = format!(
+        "royalty/{}/{}/{}/{}/{}",
+        test.app.block_info().chain_id,
+        test.ics721,
+        class_id,
+        other_recipient,
+        query_nonce(&test)
+    );
+    let signature: Signature = owner_key
+        .sign_prehash(&Sha256::digest(msg.as_bytes()))
+        .unwrap();
+    let signed_proof = ClaimProof::Signature {
+        pub_key: owner_pub_key,
+        signature: Binary::from(signature.to_bytes().as_slice()),
+    };
+    let set_other_recipient = |test: &mut Test| {
+        test.app.execute_contract(
+            not_owner.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::SetRoyaltyRecipient {
+                class_id: class_id.to_string(),
+                payment_address: other_recipient.to_string(),
+                proof: signed_proof.clone(),
+            },
+            &[],
+        )
+    };
+    set_other_recipient(&mut test).unwrap();
+    assert_eq!(query_royalty_info(&test).payment_address, other_recipient);
+    assert_eq!(query_nonce(&test), 2);
+
+    // owner sets back recipient, replaying the signed proof fails
+    test.app
+        .execute_contract(
+            creator,
+            test.ics721.clone(),
+            &ExecuteMsg::SetRoyaltyRecipient {
+                class_id: class_id.to_string(),
+                payment_address: recipient.to_string(),
+                proof: ClaimProof::Bech32 {},
+            },
+            &[],
+        )
+        .unwrap();
+    let err: ContractError = set_other_recipient(&mut test)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidOwnerProof(home_owner));
+    assert_eq!(query_royalty_info(&test).payment_address, recipient);
+}
+
+#[test]
+fn test_transfer_creatorship() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    let transfer = |test: &mut Test, sender: Addr, collection: &Addr| {
+        test.app
+            .execute_contract(
+                sender,
+                test.ics721.clone(),
+                &ExecuteMsg::TransferCreatorship {
+                    collection: collection.to_string(),
+                    channel_id: "channel-0".to_string(),
+                    new_creator: None,
+                    timeout: IbcTimeout::with_block(IbcTimeoutBlock {
+                        revision: 0,
+                        height: 10,
+                    }),
+                },
+                &[],
+            )
+            .map_err(|err| err.downcast::<ContractError>().unwrap())
+    };
+
+    // control packets must be enabled on channel
+    let source_cw721 = test.source_cw721.clone();
+    let owner = test.source_cw721_owner.clone();
+    let err = transfer(&mut test, owner.clone(), &source_cw721).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ControlPacketsDisabled("channel-0".to_string())
+    );
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    test.app
+        .execute_contract(
+            admin.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetControlPackets {
+                channel_id: "channel-0".to_string(),
+                enabled: true,
+            },
+            &[],
+        )
+        .unwrap();
+    let enabled: bool = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::ControlPackets {
+                channel_id: "channel-0".to_string(),
+            },
+        )
+        .unwrap();
+    assert!(enabled);
+
+    // only collection owner can transfer creatorship
+    let other = test.app.api().addr_make(NFT_OWNER_TARGET_CHAIN);
+    let err = transfer(&mut test, other.clone(), &source_cw721).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotCollectionOwner {
+            sender: other.to_string(),
+            class_id: source_cw721.to_string(),
+        }
+    );
+
+    let res = transfer(&mut test, owner.clone(), &source_cw721).unwrap();
+    let event = res.events.iter().find(|event| event.ty == "wasm").unwrap();
+    assert!(event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "method" && attr.value == "execute_transfer_creatorship"));
+
+    // not possible for voucher collections
+    let class_id = ClassId::new(format!(
+        "wasm.{}/{}/{}",
+        test.ics721,
+        CHANNEL_TARGET_CHAIN,
+        test.app.api().addr_make(COLLECTION_CONTRACT_SOURCE_CHAIN)
+    ));
+    let mirror = test
+        .app
+        .instantiate_contract(
+            test.source_cw721_id,
+            owner.clone(),
+            &Cw721InstantiateMsg {
+                name: "mirror".to_string(),
+                symbol: "mirror".to_string(),
+                collection_info_extension: None,
+                creator: None,
+                minter: Some(test.ics721.to_string()),
+                withdraw_address: None,
+            },
+            &[],
+            "mirror",
+            None,
+        )
+        .unwrap();
+    test.app
+        .execute_contract(
+            admin.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::RegisterVoucherContract {
+                class_id: class_id.to_string(),
+                contract: mirror.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    let err = transfer(&mut test, owner, &mirror).unwrap_err();
+    assert_eq!(err, ContractError::NotHomeCollection(mirror.to_string()));
+
+    // cw721 admin isn't ics721, still ics721 is creator of vouchers received on channels with control packets
+    test.app
+        .execute_contract(
+            admin.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetControlPackets {
+                channel_id: CHANNEL_TARGET_CHAIN.to_string(),
+                enabled: true,
+            },
+            &[],
+        )
+        .unwrap();
+    let create_voucher_creator = |test: &mut Test, channel_id: &str| {
+        let class_id = ClassId::new(format!(
+            "wasm.{}/{}/{}",
+            test.ics721,
+            channel_id,
+            test.app.api().addr_make("home_collection")
+        ));
+        test.app
+            .execute_contract(
+                test.ics721.clone(),
+                test.ics721.clone(),
+                &ExecuteMsg::Callback(CallbackMsg::CreateVouchers {
+                    receiver: test.source_cw721_owner.to_string(),
+                    create: VoucherCreation {
+                        class: Class {
+                            id: class_id.clone(),
+                            uri: None,
+                            data: None,
+                        },
+                        tokens: vec![Token {
+                            id: TokenId::new("1"),
+                            uri: None,
+                            data: None,
+                        }],
+                    },
+                }),
+                &[],
+            )
+            .unwrap();
+        let nft_contract: Addr = test
+            .app
+            .wrap()
+            .query_wasm_smart(
+                test.ics721.clone(),
+                &QueryMsg::NftContract {
+                    class_id: class_id.to_string(),
+                },
+            )
+            .unwrap();
+        let ownership: cw_ownable::Ownership<Addr> = test
+            .app
+            .wrap()
+            .query_wasm_smart(nft_contract, &Cw721QueryMsg::GetCreatorOwnership {})
+            .unwrap();
+        ownership.owner
+    };
+    assert_eq!(
+        create_voucher_creator(&mut test, CHANNEL_TARGET_CHAIN),
+        Some(test.ics721.clone())
+    );
+    // ... otherwise cw721 admin is creator
+    assert_eq!(create_voucher_creator(&mut test, "channel-2"), Some(admin));
+}
+
+#[test]
+fn test_unknown_metadata_policy() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    let unknown_data = to_json_binary("unknown format").unwrap();
+    let class_id = |test: &Test, collection: &str| {
+        ClassId::new(format!(
+            "wasm.{}/{}/{}",
+            test.ics721,
+            CHANNEL_TARGET_CHAIN,
+            test.app.api().addr_make(collection)
+        ))
+    };
+    let create_vouchers = |test: &mut Test, class_id: &ClassId| {
+        test.app.execute_contract(
+            test.ics721.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Callback(CallbackMsg::CreateVouchers {
+                receiver: test.source_cw721_owner.to_string(),
+                create: VoucherCreation {
+                    class: Class {
+                        id: class_id.clone(),
+                        uri: None,
+                        data: Some(unknown_data.clone()),
+                    },
+                    tokens: vec![Token {
+                        id: TokenId::new("1"),
+                        uri: None,
+                        data: Some(unknown_data.clone()),
+                    }],
+                },
+            }),
+            &[],
+        )
+    };
+    let set_policy = |test: &mut Test, sender: Addr, policy: Option<UnknownMetadataPolicy>| {
+        test.app.execute_contract(
+            sender,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetUnknownMetadataPolicy { policy },
+            &[],
+        )
+    };
+    let query_policy = |test: &Test| -> UnknownMetadataPolicy {
+        test.app
+            .wrap()
+            .query_wasm_smart(test.ics721.clone(), &QueryMsg::UnknownMetadataPolicy {})
+            .unwrap()
+    };
+    let query_data = |test: &Test, class_id: &ClassId| -> (Option<Binary>, Option<Binary>) {
+        let class: Option<Class> = test
+            .app
+            .wrap()
+            .query_wasm_smart(
+                test.ics721.clone(),
+                &QueryMsg::ClassMetadata {
+                    class_id: class_id.to_string(),
+                },
+            )
+            .unwrap();
+        let token: Option<Token> = test
+            .app
+            .wrap()
+            .query_wasm_smart(
+                test.ics721.clone(),
+                &QueryMsg::TokenMetadata {
+                    class_id: class_id.to_string(),
+                    token_id: "1".to_string(),
+                },
+            )
+            .unwrap();
+        (class.unwrap().data, token.unwrap().data)
+    };
+    let metadata_unknown = |res: &AppResponse| {
+        res.events.iter().any(|e| {
+            e.attributes
+                .iter()
+                .any(|a| a.key == "metadata_unknown" && a.value == "true")
+        })
+    };
+    assert_eq!(query_policy(&test), UnknownMetadataPolicy::StoreRaw);
+
+    // by default unknown metadata is stored as is
+    let store_raw = class_id(&test, "store_raw");
+    let res = create_vouchers(&mut test, &store_raw).unwrap();
+    assert!(metadata_unknown(&res));
+    assert_eq!(
+        query_data(&test, &store_raw),
+        (Some(unknown_data.clone()), Some(unknown_data.clone()))
+    );
+    // ... but not applied to the voucher collection
+    let nft_contract: Addr = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::NftContract {
+                class_id: store_raw.to_string(),
+            },
+        )
+        .unwrap();
+    let contract_info: CollectionInfoAndExtensionResponse<DefaultOptionalCollectionExtension> =
+        test.app
+            .wrap()
+            .query_wasm_smart(
+                nft_contract,
+                &Cw721QueryMsg::GetCollectionInfoAndExtension {},
+            )
+            .unwrap();
+    assert_eq!(contract_info.name, store_raw.to_string());
+
+    // only admin can set policy
+    let err: ContractError = set_policy(
+        &mut test,
+        test.source_cw721_owner.clone(),
+        Some(UnknownMetadataPolicy::Ignore),
+    )
+    .unwrap_err()
+    .downcast()
+    .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    set_policy(
+        &mut test,
+        admin.clone(),
+        Some(UnknownMetadataPolicy::Ignore),
+    )
+    .unwrap();
+    assert_eq!(query_policy(&test), UnknownMetadataPolicy::Ignore);
+    let ignore = class_id(&test, "ignore");
+    let res = create_vouchers(&mut test, &ignore).unwrap();
+    assert!(metadata_unknown(&res));
+    assert_eq!(query_data(&test, &ignore), (None, None));
+
+    set_policy(
+        &mut test,
+        admin.clone(),
+        Some(UnknownMetadataPolicy::Reject),
+    )
+    .unwrap();
+    assert_eq!(query_policy(&test), UnknownMetadataPolicy::Reject);
+    let reject = class_id(&test, "reject");
+    let err: ContractError = create_vouchers(&mut test, &reject)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert!(matches!(err, ContractError::UnknownMetadataFormat(_)));
+
+    // unset policy
+    set_policy(&mut test, admin, None).unwrap();
+    assert_eq!(query_policy(&test), UnknownMetadataPolicy::StoreRaw);
+}
+
+#[test]
+fn test_admin_set_send_nft_to_contracts() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    // disabled by default
+    let enabled: bool = test
+        .app
+        .wrap()
+        .query_wasm_smart(test.ics721.clone(), &QueryMsg::SendNftToContracts {})
+        .unwrap();
+    assert!(!enabled);
+
+    // non admin can't call
+    let non_admin = test.app.api().addr_make("not_admin");
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            non_admin,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetSendNftToContracts { enabled: true },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    test.app
+        .execute_contract(
+            admin,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetSendNftToContracts { enabled: true },
+            &[],
+        )
+        .unwrap();
+    let enabled: bool = test
+        .app
+        .wrap()
+        .query_wasm_smart(test.ics721.clone(), &QueryMsg::SendNftToContracts {})
+        .unwrap();
+    assert!(enabled);
+}
+
+#[test]
+fn test_admin_set_receiver_conversion() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    let query = |test: &Test| -> bool {
+        test.app
+            .wrap()
+            .query_wasm_smart(
+                test.ics721.clone(),
+                &QueryMsg::ReceiverConversion {
+                    channel_id: CHANNEL_TARGET_CHAIN.to_string(),
+                },
+            )
+            .unwrap()
+    };
+    // disabled by default
+    assert!(!query(&test));
+
+    // non admin can't call
+    let non_admin = test.app.api().addr_make("not_admin");
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            non_admin,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetReceiverConversion {
+                channel_id: CHANNEL_TARGET_CHAIN.to_string(),
+                enabled: true,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    test.app
+        .execute_contract(
+            admin,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetReceiverConversion {
+                channel_id: CHANNEL_TARGET_CHAIN.to_string(),
+                enabled: true,
+            },
+            &[],
+        )
+        .unwrap();
+    assert!(query(&test));
+}
+
+#[test]
+fn test_admin_set_name_service() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    let name_service_id = test.app.store_code(name_service_contract());
+    let alice = test.app.api().addr_make("alice");
+    let name_service = test
+        .app
+        .instantiate_contract(
+            name_service_id,
+            test.app.api().addr_make(ICS721_CREATOR),
+            &name_service_tester::InstantiateMsg {
+                names: vec![("alice.stars".to_string(), alice.to_string())],
+            },
+            &[],
+            "name service",
+            None,
+        )
+        .unwrap();
+    let address: String = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            name_service.clone(),
+            &NameServiceQueryMsg::AssociatedAddress {
+                name: "alice.stars".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(address, alice.to_string());
+
+    // none by default
+    let query = |test: &Test| -> Option<Addr> {
+        test.app
+            .wrap()
+            .query_wasm_smart(test.ics721.clone(), &QueryMsg::NameService {})
+            .unwrap()
+    };
+    assert_eq!(query(&test), None);
+
+    // non admin can't call
+    let non_admin = test.app.api().addr_make("not_admin");
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            non_admin,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetNameService {
+                name_service: Some(name_service.to_string()),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    test.app
+        .execute_contract(
+            admin.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetNameService {
+                name_service: Some(name_service.to_string()),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(query(&test), Some(name_service));
+
+    test.app
+        .execute_contract(
+            admin,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetNameService { name_service: None },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(query(&test), None);
+}
+
+#[test]
+fn test_inbox_claim() {
+    let mut test = Test::new(
+        false,
+        false,
+        None,
+        Some(ICS721_ADMIN_AND_PAUSER.to_string()),
+        cw721_base_contract(),
+        true,
+    );
+    let admin = test.app.api().addr_make(ICS721_ADMIN_AND_PAUSER);
+    let inbox_config = InboxConfig {
+        claim_expiry: 3600,
+        return_timeout: 600,
+    };
+    test.app
+        .execute_contract(
+            admin,
+            test.ics721.clone(),
+            &ExecuteMsg::AdminSetInbox {
+                config: Some(inbox_config.clone()),
+            },
+            &[],
+        )
+        .unwrap();
+    let config: Option<InboxConfig> = test
+        .app
+        .wrap()
+        .query_wasm_smart(test.ics721.clone(), &QueryMsg::InboxConfig {})
+        .unwrap();
+    assert_eq!(config, Some(inbox_config));
+
+    // raw receiver with a foreign prefix, holding the same data as claimer
+    let claimer = test.app.api().addr_make("claimer");
+    let (_, claimer_data) = decode(claimer.as_str()).unwrap();
+    let receiver = encode::<bech32::Bech32>(Hrp::parse("juno").unwrap(), &claimer_data).unwrap();
+
+    // NFT held in custody by ics721
+    let collection_contract_source_chain =
+        ClassId::new(test.app.api().addr_make(COLLECTION_CONTRACT_SOURCE_CHAIN));
+    let class_id = ClassId::new(format!(
+        "wasm.{}/{}/{}",
+        test.ics721, CHANNEL_TARGET_CHAIN, collection_contract_source_chain
+    ));
+    test.app
+        .execute_contract(
+            test.ics721.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Callback(CallbackMsg::CreateVouchers {
+                receiver: test.ics721.to_string(),
+                create: VoucherCreation {
+                    class: Class {
+                        id: class_id.clone(),
+                        uri: None,
+                        data: None,
+                    },
+                    tokens: vec![Token {
+                        id: TokenId::new("1"),
+                        uri: None,
+                        data: None,
+                    }],
+                },
+            }),
+            &[],
+        )
+        .unwrap();
+    let nft_contract: Addr = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::NftContract {
+                class_id: class_id.to_string(),
+            },
+        )
+        .unwrap();
+    let claim = Claim {
+        nft_contract: nft_contract.clone(),
+        sender: "juno1sender".to_string(),
+        channel_id: CHANNEL_TARGET_CHAIN.to_string(),
+        expiration: test.app.block_info().time.plus_seconds(3600),
+    };
+    test.app
+        .execute_contract(
+            test.ics721.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Callback(CallbackMsg::AddClaims {
+                receiver: receiver.clone(),
+                claims: vec![((class_id.clone(), TokenId::new("1")), claim.clone())],
+            }),
+            &[],
+        )
+        .unwrap();
+    let claims: Vec<((ClassId, TokenId), Claim)> = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            test.ics721.clone(),
+            &QueryMsg::Claims {
+                receiver: receiver.clone(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(claims, vec![((class_id, TokenId::new("1")), claim)]);
+
+    // only owner of receiver can claim
+    let not_owner = test.app.api().addr_make("not_owner");
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            not_owner,
+            test.ics721.clone(),
+            &ExecuteMsg::Claim {
+                receiver: receiver.clone(),
+                recipient: claimer.to_string(),
+                proof: ClaimProof::Bech32 {},
+                limit: None,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidClaimProof(receiver.clone()));
+
+    test.app
+        .execute_contract(
+            claimer.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Claim {
+                receiver: receiver.clone(),
+                recipient: claimer.to_string(),
+                proof: ClaimProof::Bech32 {},
+                limit: None,
+            },
+            &[],
+        )
+        .unwrap();
+    let owner: cw721::msg::OwnerOfResponse = test
+        .app
+        .wrap()
+        .query_wasm_smart(
+            nft_contract,
+            &cw721_metadata_onchain::msg::QueryMsg::OwnerOf {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(owner.owner, claimer.to_string());
+
+    // claims are removed
+    let err: ContractError = test
+        .app
+        .execute_contract(
+            claimer.clone(),
+            test.ics721.clone(),
+            &ExecuteMsg::Claim {
+                receiver: receiver.clone(),
+                recipient: claimer.to_string(),
+                proof: ClaimProof::Bech32 {},
+                limit: None,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoClaims(receiver));
+}
+
 /// In case proxy for ICS721 is defined, ICS721 only accepts receival from proxy - not from nft contract!
 #[test]
 fn test_no_receive_with_proxy() {
@@ -2308,6 +4522,7 @@ fn test_no_receive_with_proxy() {
                         height: 10,
                     }),
                     memo: None,
+                    refund_address: None,
                 })
                 .unwrap(),
             }),
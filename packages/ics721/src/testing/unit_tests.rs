@@ -2,8 +2,9 @@ use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
     from_json,
     testing::{mock_dependencies, mock_env, mock_info, MockQuerier, MOCK_CONTRACT_ADDR},
-    to_json_binary, Addr, ContractResult, CosmosMsg, Decimal, DepsMut, Empty, IbcMsg, IbcTimeout,
-    Order, QuerierResult, Response, StdResult, SubMsg, Timestamp, WasmMsg, WasmQuery,
+    to_json_binary, Addr, Attribute, ContractResult, CosmosMsg, Decimal, DepsMut, Empty, Env,
+    IbcAcknowledgement, IbcEndpoint, IbcMsg, IbcPacket, IbcPacketAckMsg, IbcPacketTimeoutMsg,
+    IbcTimeout, Order, QuerierResult, Response, StdResult, SubMsg, Timestamp, WasmMsg, WasmQuery,
 };
 use cw721::{
     msg::{
@@ -21,22 +22,29 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     execute::Ics721Execute,
-    ibc::{Ics721Ibc, INSTANTIATE_INCOMING_PROXY_REPLY_ID, INSTANTIATE_OUTGOING_PROXY_REPLY_ID},
-    msg::{InstantiateMsg, MigrateMsg},
+    hooks::Ics721Hooks,
+    ibc::{
+        Ics721Ibc, INSTANTIATE_INCOMING_PROXY_REPLY_ID, INSTANTIATE_OUTGOING_PROXY_REPLY_ID,
+        SEND_PACKET_REPLY_ID,
+    },
+    ibc_helpers::ack_success,
+    msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg as Ics721QueryMsg},
     query::{
         query_class_id_for_nft_contract, query_nft_contract_for_class_id, query_nft_contracts,
         Ics721Query,
     },
     state::{
-        ClassIdInfo, CollectionData, CLASS_ID_AND_NFT_CONTRACT_INFO, CLASS_ID_TO_CLASS,
-        CONTRACT_ADDR_LENGTH, CW721_ADMIN, CW721_CODE_ID, IBC_RECEIVE_TOKEN_METADATA,
-        INCOMING_PROXY, OUTGOING_CLASS_TOKEN_TO_CHANNEL, OUTGOING_PROXY, PO,
+        Claim, ClassIdInfo, CollectionData, InboxConfig, CLAIMS, CLASS_ID_AND_NFT_CONTRACT_INFO,
+        CLASS_ID_TO_CLASS, CONTRACT_ADDR_LENGTH, CW721_ADMIN, CW721_CODE_ID,
+        IBC_RECEIVE_TOKEN_METADATA, INBOX_CONFIG, INCOMING_PROXY, OUTGOING_CLASS_TOKEN_TO_CHANNEL,
+        OUTGOING_PROXY, PO, RETURNED_CLAIMS,
     },
     utils::get_collection_data,
+    ContractError,
 };
 use ics721_types::{
     ibc_types::{IbcOutgoingMsg, NonFungibleTokenPacketData},
-    token_types::{ClassId, Token, TokenId},
+    token_types::{ClassId, ClassToken, Token, TokenId},
 };
 
 const NFT_CONTRACT_1: &str = "nft1";
@@ -61,6 +69,7 @@ impl Ics721Execute<Empty> for Ics721Contract {
         get_collection_data(deps, sender).map(Option::Some)
     }
 }
+impl Ics721Hooks<Empty> for Ics721Contract {}
 impl Ics721Ibc<Empty> for Ics721Contract {}
 impl Ics721Query for Ics721Contract {}
 
@@ -77,6 +86,7 @@ impl Ics721Execute<Empty> for Ics721ContractNoClassData {
         Ok(None)
     }
 }
+impl Ics721Hooks<Empty> for Ics721ContractNoClassData {}
 impl Ics721Ibc<Empty> for Ics721ContractNoClassData {}
 impl Ics721Query for Ics721ContractNoClassData {}
 
@@ -407,6 +417,7 @@ fn test_receive_nft() {
             channel_id: "channel-1".to_string(),
             timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(42)),
             memo: None,
+            refund_address: None,
         })
         .unwrap();
 
@@ -522,6 +533,7 @@ fn test_receive_nft() {
             channel_id: "channel-1".to_string(),
             timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(42)),
             memo: None,
+            refund_address: None,
         })
         .unwrap();
 
@@ -623,6 +635,7 @@ fn test_receive_nft() {
             channel_id: "channel-1".to_string(),
             timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(42)),
             memo: None,
+            refund_address: None,
         })
         .unwrap();
 
@@ -708,6 +721,7 @@ fn test_receive_nft() {
             channel_id: "channel-1".to_string(),
             timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(42)),
             memo: None,
+            refund_address: None,
         })
         .unwrap();
 
@@ -793,6 +807,7 @@ fn test_receive_nft() {
             channel_id: "channel-1".to_string(),
             timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(42)),
             memo: None,
+            refund_address: None,
         })
         .unwrap();
 
@@ -878,6 +893,7 @@ fn test_receive_nft() {
             channel_id: "channel-1".to_string(),
             timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(42)),
             memo: None,
+            refund_address: None,
         })
         .unwrap();
 
@@ -896,22 +912,25 @@ fn test_receive_nft() {
         let channel_id = "channel-1".to_string();
         assert_eq!(
             res.messages[0],
-            SubMsg::new(CosmosMsg::<Empty>::Ibc(IbcMsg::SendPacket {
-                channel_id: channel_id.clone(),
-                timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(42)),
-                data: to_json_binary(&NonFungibleTokenPacketData {
-                    class_id: ClassId::new(NFT_CONTRACT_1),
-                    class_uri: None,
-                    class_data: None,
-                    token_data: None,
-                    token_ids: vec![TokenId::new(token_id)],
-                    token_uris: Some(vec!["https://moonphase.is/image.svg".to_string()]),
-                    sender,
-                    receiver: "callum".to_string(),
-                    memo: None,
-                })
-                .unwrap()
-            }))
+            SubMsg::reply_on_success(
+                CosmosMsg::<Empty>::Ibc(IbcMsg::SendPacket {
+                    channel_id: channel_id.clone(),
+                    timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(42)),
+                    data: to_json_binary(&NonFungibleTokenPacketData {
+                        class_id: ClassId::new(NFT_CONTRACT_1),
+                        class_uri: None,
+                        class_data: None,
+                        token_data: None,
+                        token_ids: vec![TokenId::new(token_id)],
+                        token_uris: Some(vec!["https://moonphase.is/image.svg".to_string()]),
+                        sender,
+                        receiver: "callum".to_string(),
+                        memo: None,
+                    })
+                    .unwrap()
+                }),
+                SEND_PACKET_REPLY_ID
+            )
         );
 
         // check outgoing classID and tokenID
@@ -1078,6 +1097,119 @@ fn test_callback_mint() {
     }
 }
 
+#[test]
+fn test_return_expired_claims() {
+    let mut deps = mock_dependencies();
+    deps.querier.update_wasm(mock_querier);
+    let mut env = mock_env();
+    PO.set_pauser(&mut deps.storage, &deps.api, None).unwrap();
+    INBOX_CONFIG
+        .save(
+            &mut deps.storage,
+            &InboxConfig {
+                claim_expiry: 3600,
+                return_timeout: 600,
+            },
+        )
+        .unwrap();
+    let claim = |expiration: Timestamp| Claim {
+        nft_contract: Addr::unchecked(NFT_CONTRACT_1),
+        sender: "sender".to_string(),
+        channel_id: "channel-1".to_string(),
+        expiration,
+    };
+    let key = |token_id: &str| {
+        (
+            "receiver".to_string(),
+            (ClassId::new(NFT_CONTRACT_1), TokenId::new(token_id)),
+        )
+    };
+    CLAIMS
+        .save(&mut deps.storage, key("1"), &claim(env.block.time))
+        .unwrap();
+    CLAIMS
+        .save(
+            &mut deps.storage,
+            key("2"),
+            &claim(env.block.time.plus_seconds(1)),
+        )
+        .unwrap();
+    let return_expired_claims = |deps: DepsMut, env: Env| -> IbcPacket {
+        let res = Ics721Contract::default()
+            .execute(
+                deps,
+                env,
+                mock_info("anyone", &[]),
+                ExecuteMsg::ReturnExpiredClaims {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        match res.messages[0].msg.clone() {
+            CosmosMsg::Ibc(IbcMsg::SendPacket {
+                channel_id,
+                data,
+                timeout,
+            }) => {
+                let packet_data: NonFungibleTokenPacketData = from_json(&data).unwrap();
+                assert_eq!(packet_data.receiver, "sender");
+                IbcPacket::new(
+                    data,
+                    IbcEndpoint {
+                        port_id: "wasm.address1".to_string(),
+                        channel_id,
+                    },
+                    IbcEndpoint {
+                        port_id: "stars.address1".to_string(),
+                        channel_id: "channel-0".to_string(),
+                    },
+                    1,
+                    timeout,
+                )
+            }
+            _ => panic!("unexpected message type"),
+        }
+    };
+
+    // only expired claim is returned, and removed once acked
+    let packet = return_expired_claims(deps.as_mut(), env.clone());
+    assert!(!CLAIMS.has(&deps.storage, key("1")));
+    assert!(CLAIMS.has(&deps.storage, key("2")));
+    Ics721Contract::default()
+        .ibc_packet_ack(
+            deps.as_mut(),
+            env.clone(),
+            IbcPacketAckMsg::new(
+                IbcAcknowledgement::new(ack_success()),
+                packet,
+                Addr::unchecked("relayer"),
+            ),
+        )
+        .unwrap();
+    assert!(!CLAIMS.has(&deps.storage, key("1")));
+    assert!(RETURNED_CLAIMS.is_empty(&deps.storage));
+
+    // on timeout, claim is restored instead of refunding NFT to ics721
+    env.block.time = env.block.time.plus_seconds(1);
+    let packet = return_expired_claims(deps.as_mut(), env.clone());
+    assert!(!CLAIMS.has(&deps.storage, key("2")));
+    let res = Ics721Contract::default()
+        .ibc_packet_timeout(
+            deps.as_mut(),
+            env.clone(),
+            IbcPacketTimeoutMsg::new(packet, Addr::unchecked("relayer")),
+        )
+        .unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(
+        CLAIMS.load(&deps.storage, key("2")).unwrap(),
+        claim(env.block.time)
+    );
+    assert!(RETURNED_CLAIMS.is_empty(&deps.storage));
+}
+
 #[test]
 fn test_receive_sets_uri() {
     let mut querier = MockQuerier::default();
@@ -1095,6 +1227,7 @@ fn test_receive_sets_uri() {
         channel_id: "channel-1".to_string(),
         timeout: IbcTimeout::with_timestamp(Timestamp::from_nanos(42)),
         memo: None,
+        refund_address: None,
     })
     .unwrap();
 
@@ -1270,6 +1403,26 @@ fn test_migrate() {
         )
         .unwrap();
 
+    // channel entries stored before as plain maps, without channel index
+    let legacy_outgoing_channels: Map<(ClassId, TokenId), String> = Map::new("h");
+    for (token_id, channel) in [("1", "channel-0"), ("2", "channel-1")] {
+        legacy_outgoing_channels
+            .save(
+                deps.as_mut().storage,
+                (ClassId::new(CLASS_ID_1), TokenId::new(token_id)),
+                &channel.to_string(),
+            )
+            .unwrap();
+    }
+    let legacy_incoming_channels: Map<(ClassId, TokenId), String> = Map::new("i");
+    legacy_incoming_channels
+        .save(
+            deps.as_mut().storage,
+            (ClassId::new(CLASS_ID_2), TokenId::new("1")),
+            &"channel-1".to_string(),
+        )
+        .unwrap();
+
     // migrate
     Ics721Contract {}
         .migrate(deps.as_mut(), env.clone(), msg)
@@ -1312,4 +1465,109 @@ fn test_migrate() {
     let class_id_2 =
         query_class_id_for_nft_contract(deps.as_ref(), NFT_CONTRACT_2.to_string()).unwrap();
     assert_eq!(class_id_2, Some(ClassId::new(CLASS_ID_2)));
+
+    // channel indexes are not rebuilt by migrate, but paged by admin
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::ContractInfo { .. } => QuerierResult::Ok(ContractResult::Ok(
+            to_json_binary(&ContractInfoResponse {
+                code_id: 1,
+                creator: OWNER_ADDR.to_string(),
+                admin: Some(ADMIN_ADDR.to_string()),
+                pinned: false,
+                ibc_port: None,
+            })
+            .unwrap(),
+        )),
+        _ => unimplemented!(),
+    });
+    let migrate_channel_indexes =
+        |deps: DepsMut, sender: &str, incoming: bool, start_after: Option<ClassToken>| {
+            Ics721Contract {}.execute(
+                deps,
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::MigrateChannelIndexes {
+                    incoming,
+                    start_after,
+                    limit: Some(1),
+                },
+            )
+        };
+    let err = migrate_channel_indexes(deps.as_mut(), OWNER_ADDR, false, None).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = migrate_channel_indexes(deps.as_mut(), ADMIN_ADDR, false, None).unwrap();
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("last_token_id", "1")));
+    let res = migrate_channel_indexes(
+        deps.as_mut(),
+        ADMIN_ADDR,
+        false,
+        Some(ClassToken {
+            class_id: ClassId::new(CLASS_ID_1),
+            token_id: TokenId::new("1"),
+        }),
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("last_token_id", "2")));
+    let res = migrate_channel_indexes(
+        deps.as_mut(),
+        ADMIN_ADDR,
+        false,
+        Some(ClassToken {
+            class_id: ClassId::new(CLASS_ID_1),
+            token_id: TokenId::new("2"),
+        }),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&Attribute::new("migrated", "0")));
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("last_token_id", "none")));
+    migrate_channel_indexes(deps.as_mut(), ADMIN_ADDR, true, None).unwrap();
+
+    // test channel indexes are rebuilt
+    let query_channels = |msg: Ics721QueryMsg| -> Vec<((String, String), String)> {
+        from_json(
+            Ics721Contract {}
+                .query(deps.as_ref(), env.clone(), msg)
+                .unwrap(),
+        )
+        .unwrap()
+    };
+    let outgoing_channels = query_channels(Ics721QueryMsg::OutgoingChannels {
+        channel_id: Some("channel-1".to_string()),
+        class_id: None,
+        start_after: None,
+        limit: None,
+    });
+    assert_eq!(
+        outgoing_channels,
+        [(
+            (CLASS_ID_1.to_string(), "2".to_string()),
+            "channel-1".to_string()
+        )]
+    );
+    let incoming_channels = query_channels(Ics721QueryMsg::IncomingChannels {
+        channel_id: Some("channel-1".to_string()),
+        class_id: Some(CLASS_ID_2.to_string()),
+        start_after: None,
+        limit: None,
+    });
+    assert_eq!(
+        incoming_channels,
+        [(
+            (CLASS_ID_2.to_string(), "1".to_string()),
+            "channel-1".to_string()
+        )]
+    );
+    let outgoing_channels = query_channels(Ics721QueryMsg::OutgoingChannels {
+        channel_id: None,
+        class_id: Some(CLASS_ID_2.to_string()),
+        start_after: None,
+        limit: None,
+    });
+    assert!(outgoing_channels.is_empty());
 }
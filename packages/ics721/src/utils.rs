@@ -1,8 +1,14 @@
-use cosmwasm_std::{Addr, DepsMut, Empty, Env, StdResult};
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, ContractInfoResponse, Deps, DepsMut, Empty, Env, StdError,
+    StdResult,
+};
 use cw721::msg::NumTokensResponse;
 use cw_ownable::Ownership;
+use ics721_types::token_types::Class;
 
-use crate::state::{CollectionData, UniversalCollectionInfoResponse};
+use crate::state::{
+    CollectionData, UniversalCollectionInfoResponse, VoucherFlavour, VOUCHER_PROFILES,
+};
 
 pub fn get_collection_data(deps: &DepsMut, collection: &Addr) -> StdResult<CollectionData> {
     // cw721 v0.19 and higher holds creator ownership (cw-ownable storage) in the contract
@@ -60,13 +66,162 @@ pub fn get_collection_data(deps: &DepsMut, collection: &Addr) -> StdResult<Colle
     })
 }
 
+/// Query the minter of a cw721 contract, supporting all versions.
+pub fn query_minter(deps: Deps, collection: &str) -> StdResult<Option<String>> {
+    // cw721 v0.17 and higher holds minter ownership (cw-ownable storage) in the contract
+    let ownership: StdResult<Ownership<Addr>> = deps.querier.query_wasm_smart(
+        collection,
+        &cw721_base_018::msg::QueryMsg::Ownership::<Addr> {},
+    );
+    match ownership {
+        Ok(ownership) => Ok(ownership.owner.map(|a| a.to_string())),
+        Err(_) => {
+            // cw721 v0.16 and lower holds minter (simple string storage)
+            let minter_response: cw721_base_016::msg::MinterResponse = deps
+                .querier
+                .query_wasm_smart(collection, &cw721_base_016::QueryMsg::Minter::<Empty> {})?;
+            Ok(Some(minter_response.minter))
+        }
+    }
+}
+
 /// Convert owner chain address (e.g. `juno1XXX`) to target owner chain address (e.g. `stars1XXX`).
 pub fn convert_owner_chain_address(env: &Env, source_owner: &str) -> StdResult<String> {
     // convert the source owner (e.g. `juno1XXX`) to target owner (e.g. `stars1XXX`)
-    let (_source_hrp, source_data) = bech32::decode(source_owner).unwrap();
+    let (_source_hrp, source_data) = bech32::decode(source_owner).map_err(|err| {
+        StdError::generic_err(format!("Invalid bech32 address {source_owner}: {err}"))
+    })?;
     // detect target hrp (e.g. `stars`) using contract address
-    let (target_hrp, _target_data) = bech32::decode(env.contract.address.as_str()).unwrap();
+    let (target_hrp, _target_data) =
+        bech32::decode(env.contract.address.as_str()).map_err(|err| {
+            StdError::generic_err(format!(
+                "Invalid bech32 contract address {}: {err}",
+                env.contract.address
+            ))
+        })?;
     // convert source owner to target owner
-    let target_owner = bech32::encode::<bech32::Bech32>(target_hrp, &source_data).unwrap();
+    let target_owner =
+        bech32::encode::<bech32::Bech32>(target_hrp, &source_data).map_err(|err| {
+            StdError::generic_err(format!("Failed converting address {source_owner}: {err}"))
+        })?;
     Ok(target_owner)
 }
+
+/// Instantiate msg of a debt-voucher collection for cw721 v0.16 - v0.18. In case of v0.19 and custom flavours
+/// none is returned, since these are built by `Ics721Execute::init_msg`.
+pub fn legacy_voucher_init_msg(
+    deps: Deps,
+    env: &Env,
+    class: &Class,
+    collection_data: Option<&CollectionData>,
+    flavour: &VoucherFlavour,
+) -> StdResult<Option<Binary>> {
+    // use by default ClassId, in case there's no class data with name and symbol
+    let (name, symbol) = collection_data.map_or_else(
+        || (class.id.to_string(), class.id.to_string()),
+        |collection_data| (collection_data.name.clone(), collection_data.symbol.clone()),
+    );
+    // voucher profile takes precedence over collection data
+    let (name, symbol) = match VOUCHER_PROFILES.may_load(deps.storage, class.id.clone())? {
+        Some(profile) => (
+            profile.name.unwrap_or(name),
+            profile.symbol.unwrap_or(symbol),
+        ),
+        None => (name, symbol),
+    };
+    let minter = env.contract.address.to_string();
+    let msg = match flavour {
+        VoucherFlavour::V016 => to_json_binary(&cw721_base_016::msg::InstantiateMsg {
+            name,
+            symbol,
+            minter,
+        })?,
+        VoucherFlavour::V017 => to_json_binary(&cw721_base_017::msg::InstantiateMsg {
+            name,
+            symbol,
+            minter,
+        })?,
+        VoucherFlavour::V018 => {
+            // use ics721 creator for withdraw address
+            let ContractInfoResponse { creator, .. } = deps
+                .querier
+                .query_wasm_contract_info(env.contract.address.to_string())?;
+            to_json_binary(&cw721_base_018::msg::InstantiateMsg {
+                name,
+                symbol,
+                minter,
+                withdraw_address: Some(creator),
+            })?
+        }
+        VoucherFlavour::V019 => return Ok(None),
+    };
+    Ok(Some(msg))
+}
+
+/// Mint msg of a debt-voucher for cw721 v0.16 - v0.18. Older versions have no on-chain metadata,
+/// so only the token URI is passed. In case of v0.19 and custom flavours none is returned,
+/// since these are built by `Ics721Execute::mint_msg`.
+pub fn legacy_voucher_mint_msg(
+    flavour: &VoucherFlavour,
+    token_id: String,
+    token_uri: Option<String>,
+    owner: String,
+) -> StdResult<Option<Binary>> {
+    let msg = match flavour {
+        VoucherFlavour::V016 => to_json_binary(&cw721_base_016::msg::ExecuteMsg::<
+            Option<Empty>,
+            Empty,
+        >::Mint(cw721_base_016::msg::MintMsg {
+            token_id,
+            owner,
+            token_uri,
+            extension: None,
+        }))?,
+        VoucherFlavour::V017 => to_json_binary(&cw721_base_017::msg::ExecuteMsg::<
+            Option<Empty>,
+            Empty,
+        >::Mint {
+            token_id,
+            owner,
+            token_uri,
+            extension: None,
+        })?,
+        VoucherFlavour::V018 => to_json_binary(&cw721_base_018::msg::ExecuteMsg::<
+            Option<Empty>,
+            Empty,
+        >::Mint {
+            token_id,
+            owner,
+            token_uri,
+            extension: None,
+        })?,
+        VoucherFlavour::V019 => return Ok(None),
+    };
+    Ok(Some(msg))
+}
+
+#[cfg(test)]
+mod test {
+    use cosmwasm_std::{testing::mock_env, Addr};
+
+    use super::convert_owner_chain_address;
+
+    #[test]
+    fn test_convert_owner_chain_address() {
+        let encode = |hrp: &str, data: &[u8]| {
+            bech32::encode::<bech32::Bech32>(bech32::Hrp::parse(hrp).unwrap(), data).unwrap()
+        };
+        let mut env = mock_env();
+        env.contract.address = Addr::unchecked(encode("stars", &[1; 32]));
+        let juno_owner = encode("juno", &[2; 20]);
+        assert_eq!(
+            convert_owner_chain_address(&env, &juno_owner).unwrap(),
+            encode("stars", &[2; 20])
+        );
+
+        // errors instead of panicking
+        convert_owner_chain_address(&env, "invalid").unwrap_err();
+        env.contract.address = Addr::unchecked("cosmos2contract");
+        convert_owner_chain_address(&env, &juno_owner).unwrap_err();
+    }
+}